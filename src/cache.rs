@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::Context;
+use http::header::{HeaderName, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+use serde::{Deserialize, Serialize};
+use tracing::{debug_span, warn};
+
+use crate::retry::retry_delay;
+
+/// Stores the HTTP responses on disk to replay them with conditional requests.
+///
+/// GitHub doesn't count the `304 Not Modified` responses against the rate limit,
+/// the unchanged responses are served from the disk instead of being downloaded.
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    directory: PathBuf,
+}
+
+/// A response stored in the cache, the body is stored next to it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// The `Link` header, used to follow the pagination.
+    pub link: Option<String>,
+}
+
+impl HttpCache {
+    pub fn new(directory: impl Into<PathBuf>) -> HttpCache {
+        HttpCache { directory: directory.into() }
+    }
+
+    /// Returns the stored response of this URL and its body, if any.
+    pub fn get(&self, url: &str) -> Option<(CachedResponse, Vec<u8>)> {
+        let (metadata_path, body_path) = self.paths(url);
+        let metadata = std::fs::read_to_string(metadata_path).ok()?;
+        let response: CachedResponse = serde_json::from_str(&metadata).ok()?;
+        let body = std::fs::read(body_path).ok()?;
+        // Protects against the very unlikely hash collisions
+        (response.url == url).then_some((response, body))
+    }
+
+    /// Stores a response, only if it can be revalidated later.
+    pub fn insert(&self, response: &CachedResponse, body: &[u8]) -> anyhow::Result<()> {
+        if response.etag.is_none() && response.last_modified.is_none() {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&self.directory)
+            .with_context(|| format!("while creating {}", self.directory.display()))?;
+        let (metadata_path, body_path) = self.paths(&response.url);
+        write_atomically(&body_path, body)?;
+        write_atomically(&metadata_path, &serde_json::to_vec(response)?)
+    }
+
+    /// The headers to send to only receive the body if it changed.
+    pub fn conditional_headers(response: &CachedResponse) -> Vec<(HeaderName, &str)> {
+        let mut headers = Vec::new();
+        if let Some(etag) = &response.etag {
+            headers.push((IF_NONE_MATCH, etag.as_str()));
+        }
+        if let Some(last_modified) = &response.last_modified {
+            headers.push((IF_MODIFIED_SINCE, last_modified.as_str()));
+        }
+        headers
+    }
+
+    /// Downloads a file with `ureq`, or reads it from the cache if it didn't change.
+    /// The transient failures are retried, this function blocks the thread.
+    pub fn download(&self, url: &str) -> anyhow::Result<Vec<u8>> {
+        let _span = debug_span!("download", url).entered();
+        let cached = self.get(url);
+        let mut request = ureq::get(url);
+        if let Some((response, _)) = &cached {
+            for (name, value) in HttpCache::conditional_headers(response) {
+                request = request.set(name.as_str(), value);
+            }
+        }
+
+        let mut attempt = 0;
+        let response = loop {
+            let error = match request.clone().call() {
+                Ok(response) => break response,
+                Err(error) => error,
+            };
+            let delay = match &error {
+                ureq::Error::Status(status, response) => {
+                    retry_delay(attempt, Some(*status), |name| {
+                        response.header(name).map(String::from)
+                    })
+                }
+                ureq::Error::Transport(_) => retry_delay(attempt, None, |_| None),
+            };
+            match delay {
+                Some(delay) => {
+                    warn!("{error}, retrying in {delay:?}");
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+                None => return Err(error.into()),
+            }
+        };
+        match cached {
+            Some((_, body)) if response.status() == 304 => Ok(body),
+            _ => {
+                let cached = CachedResponse {
+                    url: url.to_string(),
+                    etag: response.header("ETag").map(ToString::to_string),
+                    last_modified: response.header("Last-Modified").map(ToString::to_string),
+                    link: None,
+                };
+                let mut body = Vec::new();
+                response.into_reader().read_to_end(&mut body)?;
+                self.insert(&cached, &body)?;
+                Ok(body)
+            }
+        }
+    }
+
+    fn paths(&self, url: &str) -> (PathBuf, PathBuf) {
+        let name = format!("{:016x}", stable_hash(url));
+        (self.directory.join(format!("{name}.json")), self.directory.join(format!("{name}.body")))
+    }
+}
+
+/// Hashes with FNV-1a, the files named after it must keep their names
+/// across the Rust versions, unlike the ones of the `DefaultHasher`.
+pub(crate) fn stable_hash(s: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    s.bytes().fold(OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME))
+}
+
+/// Writes into a temporary file first to never leave a partially written file.
+pub(crate) fn write_atomically(path: &Path, content: &[u8]) -> anyhow::Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let temporary = path.with_extension(format!("{}-{count}.tmp", std::process::id()));
+    std::fs::write(&temporary, content)
+        .with_context(|| format!("while writing {}", temporary.display()))?;
+    std::fs::rename(&temporary, path).with_context(|| format!("while writing {}", path.display()))
+}
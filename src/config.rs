@@ -0,0 +1,225 @@
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context;
+use serde::Deserialize;
+use url::Url;
+
+use crate::fixtures::Fixtures;
+use crate::transform::Transforms;
+
+/// The default path of the configuration file.
+pub const CONFIG_FILE_NAME: &str = "blog.toml";
+
+/// The configuration of the blog, read from the `blog.toml` file.
+///
+/// The `GITHUB_REPOSITORY`, `EMAIL_ADDRESS` and `GITHUB_TOKEN` environment
+/// variables override the values defined in the file.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The `owner/repo` identifier of the GitHub repository of the blog.
+    pub repository: Option<String>,
+    /// The email address displayed in the RSS feed.
+    pub email_address: Option<String>,
+    pub github_token: Option<String>,
+    /// The homepage of the blog, fetched from the repository when missing.
+    pub homepage: Option<Url>,
+    /// The description of the blog displayed on the homepage and the social cards.
+    pub description: String,
+    /// The label an issue must have to be published as an article,
+    /// every local post is published whatever its labels.
+    pub label: String,
+    /// The label adding a table of contents to an article.
+    pub toc_label: String,
+    /// Adds a table of contents to the articles with at least this many headings.
+    pub toc_min_headings: Option<usize>,
+    /// The maximum number of characters of the synopsis taken from the first
+    /// paragraph of the articles without one.
+    pub synopsis_length: usize,
+    /// The GitHub API the articles are fetched with.
+    pub api: Api,
+    /// The base URL of the GitHub API, to use GitHub Enterprise or a mock server.
+    pub api_url: Url,
+    /// The number of issues fetched per request.
+    pub per_page: u8,
+    /// The maximum number of articles rendered at the same time,
+    /// and of images downloaded at the same time for an article.
+    pub concurrency: usize,
+    /// Builds from a directory of Markdown files instead of the GitHub issues.
+    pub posts_directory: Option<PathBuf>,
+    /// The directory in which the website is generated.
+    pub output: PathBuf,
+    /// The files and directories copied into the output at the same relative path.
+    pub assets: Vec<PathBuf>,
+    /// The image used as the social card of the homepage.
+    pub homepage_preview: PathBuf,
+    /// The domain of the Plausible analytics, disabled when missing.
+    pub plausible_domain: Option<String>,
+    /// The domain displayed on the previews, defaults to the homepage one.
+    pub preview_domain: Option<String>,
+    /// What to do when an article fails to render.
+    pub on_article_error: ErrorPolicy,
+    /// Where to write the JSON report of the build.
+    pub report: PathBuf,
+    /// The directory in which the API responses and images are cached between builds.
+    pub cache: PathBuf,
+    /// Only builds the article with this slug, defined on the command line.
+    /// The other articles are copied from the previous output.
+    #[serde(skip)]
+    pub only: Option<String>,
+    /// Renders all the articles again, even the unchanged ones, defined on the command line.
+    #[serde(skip)]
+    pub force: bool,
+    /// Records or replays the network requests, defined on the command line.
+    #[serde(skip)]
+    pub fixtures: Fixtures,
+    /// The rewrites of the HTML of the articles, register yours before building.
+    #[serde(skip)]
+    pub transforms: Transforms,
+    /// Where the images of the previews are fetched from, defined in code.
+    #[serde(skip)]
+    pub preview_images: PreviewImages,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            repository: None,
+            email_address: None,
+            github_token: None,
+            homepage: None,
+            description: String::new(),
+            label: "article".to_string(),
+            toc_label: "toc".to_string(),
+            toc_min_headings: None,
+            synopsis_length: 200,
+            api: Api::Rest,
+            api_url: Url::parse("https://api.github.com").unwrap(),
+            per_page: 50,
+            concurrency: 8,
+            posts_directory: None,
+            output: PathBuf::from("output"),
+            assets: [
+                "assets/script.js",
+                "assets/balls.js",
+                "assets/matter.min.js",
+                "assets/tiny-utterances.js",
+                "assets/style.css",
+                "assets/tiny-utterances.css",
+                "assets/bootstrap.min.css",
+                "assets/starry-night.css",
+                "assets/keys",
+            ]
+            .iter()
+            .map(PathBuf::from)
+            .collect(),
+            homepage_preview: PathBuf::from("assets/preview/homepage.png"),
+            plausible_domain: None,
+            preview_domain: None,
+            on_article_error: ErrorPolicy::Fail,
+            report: PathBuf::from("build-report.json"),
+            cache: PathBuf::from(".http-cache"),
+            only: None,
+            force: false,
+            fixtures: Fixtures::Off,
+            transforms: Transforms::default(),
+            preview_images: PreviewImages::default(),
+        }
+    }
+}
+
+/// Rewrites the URLs of the images drawn on the previews, the keys hosted on
+/// the blog and the profile picture of the author on GitHub, before fetching them.
+/// The tests use it to fetch them from a mock server. They are fetched as is by default.
+#[derive(Clone, Default)]
+pub struct PreviewImages {
+    resolve: Option<Arc<ResolveImage>>,
+}
+
+type ResolveImage = dyn Fn(&str) -> String + Send + Sync;
+
+impl PreviewImages {
+    pub fn new(resolve: impl Fn(&str) -> String + Send + Sync + 'static) -> PreviewImages {
+        PreviewImages { resolve: Some(Arc::new(resolve)) }
+    }
+
+    /// The URL to fetch the image from.
+    pub fn resolve(&self, url: &str) -> String {
+        match &self.resolve {
+            Some(resolve) => resolve(url),
+            None => url.to_string(),
+        }
+    }
+}
+
+impl fmt::Debug for PreviewImages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let resolver = if self.resolve.is_some() { "custom" } else { "default" };
+        f.debug_tuple("PreviewImages").field(&resolver).finish()
+    }
+}
+
+/// What to do when an article fails to render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorPolicy {
+    /// Renders the other articles but fails the build.
+    Fail,
+    /// Publishes the previous version of the article, if any.
+    KeepPrevious,
+}
+
+/// The GitHub API the articles are fetched with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Api {
+    /// A few requests per article, works without a token.
+    Rest,
+    /// A handful of requests for the whole blog, requires a token.
+    #[serde(rename = "graphql")]
+    GraphQl,
+}
+
+impl Config {
+    /// Reads the configuration file, if it exists, and applies the environment variables.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Config> {
+        let path = path.as_ref();
+        let mut config = match std::fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content)
+                .with_context(|| format!("while parsing {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default(),
+            Err(e) => return Err(e).with_context(|| format!("while reading {}", path.display())),
+        };
+
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    fn apply_env(&mut self) -> anyhow::Result<()> {
+        let var = |name| env::var(name).ok().filter(|s| !s.is_empty());
+
+        if let Some(repository) = var("GITHUB_REPOSITORY") {
+            self.repository = Some(repository);
+        }
+        if let Some(email_address) = var("EMAIL_ADDRESS") {
+            self.email_address = Some(email_address);
+        }
+        if let Some(token) = var("GITHUB_TOKEN") {
+            self.github_token = Some(token);
+        }
+        if let Some(directory) = var("POSTS_DIRECTORY") {
+            self.posts_directory = Some(PathBuf::from(directory));
+        }
+        if let Some(api_url) = var("GITHUB_API_URL") {
+            self.api_url = Url::parse(&api_url).context("invalid `GITHUB_API_URL`")?;
+        }
+        if let Some(homepage) = var("HOMEPAGE_URL") {
+            self.homepage = Some(Url::parse(&homepage).context("invalid `HOMEPAGE_URL`")?);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{stable_hash, write_atomically};
+
+/// Records the responses of every network request into a directory,
+/// or replays them from this directory without touching the network.
+///
+/// This is useful to reproduce a broken build locally and to test
+/// the whole build against a frozen snapshot of the blog.
+#[derive(Debug, Clone, Default)]
+pub enum Fixtures {
+    /// The requests are sent to the network as usual.
+    #[default]
+    Off,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// A recorded response, the body is stored next to it.
+#[derive(Debug, Serialize, Deserialize)]
+struct Fixture {
+    /// The URL of the request, or the query for the GraphQL requests.
+    request: String,
+    /// The `Link` header, used to follow the pagination.
+    link: Option<String>,
+}
+
+impl Fixtures {
+    pub fn is_replaying(&self) -> bool {
+        matches!(self, Fixtures::Replay(_))
+    }
+
+    /// Returns the recorded `Link` header and body of the request when replaying,
+    /// fails if the request hasn't been recorded. Returns `None` otherwise.
+    pub fn replay(&self, request: &str) -> anyhow::Result<Option<(Option<String>, Vec<u8>)>> {
+        let Fixtures::Replay(directory) = self else { return Ok(None) };
+        let (metadata_path, body_path) = paths(directory, request);
+        let not_recorded = || format!("{request} has not been recorded in {}", directory.display());
+
+        let metadata = std::fs::read_to_string(metadata_path).with_context(not_recorded)?;
+        let fixture: Fixture = serde_json::from_str(&metadata).with_context(not_recorded)?;
+        anyhow::ensure!(fixture.request == request, not_recorded());
+        let body = std::fs::read(body_path).with_context(not_recorded)?;
+        Ok(Some((fixture.link, body)))
+    }
+
+    /// Stores the response of the request when recording, does nothing otherwise.
+    pub fn record(&self, request: &str, link: Option<&str>, body: &[u8]) -> anyhow::Result<()> {
+        let Fixtures::Record(directory) = self else { return Ok(()) };
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("while creating {}", directory.display()))?;
+
+        let (metadata_path, body_path) = paths(directory, request);
+        let fixture = Fixture { request: request.to_string(), link: link.map(String::from) };
+        write_atomically(&body_path, body)?;
+        write_atomically(&metadata_path, &serde_json::to_vec_pretty(&fixture)?)
+    }
+}
+
+fn paths(directory: &Path, request: &str) -> (PathBuf, PathBuf) {
+    let name = format!("{:016x}", stable_hash(request));
+    (directory.join(format!("{name}.json")), directory.join(format!("{name}.body")))
+}
@@ -0,0 +1,344 @@
+use std::future::Future;
+use std::sync::Mutex;
+
+use anyhow::Context;
+use http::header::{HeaderMap, HeaderValue, ACCEPT, ETAG, LAST_MODIFIED, LINK};
+use http::StatusCode;
+use octocrab::models::issues::Issue;
+use octocrab::models::reactions::{Reaction, ReactionContent};
+use octocrab::models::timelines::{Rename, TimelineEvent};
+use octocrab::service::middleware::retry::RetryConfig;
+use octocrab::{format_media_type, Octocrab, OctocrabBuilder};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::cache::{CachedResponse, HttpCache};
+use crate::config::Config;
+use crate::fixtures::Fixtures;
+use crate::metadata::Metadata;
+use crate::retry::{retry_delay, RateLimit};
+use crate::source::{Article, ArticleHistory, ArticleSource, Author, ReactionCounts};
+
+/// Fetches the articles from the issues of a GitHub repository
+/// that are labelled with the configured label, `article` by default.
+pub struct GitHubSource {
+    octocrab: Octocrab,
+    owner: String,
+    repository: String,
+    label: String,
+    per_page: u8,
+    api_url: Url,
+    cache: HttpCache,
+    fixtures: Fixtures,
+    /// The quota of requests left, according to the last response.
+    rate_limit: Mutex<Option<RateLimit>>,
+    /// The issues skipped or degraded while listing the articles.
+    warnings: Mutex<Vec<String>>,
+}
+
+impl GitHubSource {
+    pub fn new(
+        owner: impl Into<String>,
+        repository: impl Into<String>,
+        config: &Config,
+    ) -> anyhow::Result<GitHubSource> {
+        Ok(GitHubSource {
+            octocrab: build_octocrab(config)?,
+            owner: owner.into(),
+            repository: repository.into(),
+            label: config.label.clone(),
+            per_page: config.per_page,
+            api_url: config.api_url.clone(),
+            cache: HttpCache::new(&config.cache),
+            fixtures: config.fixtures.clone(),
+            rate_limit: Mutex::new(None),
+            warnings: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// The homepage URL of the blog defined on the repository.
+    pub async fn homepage(&self) -> anyhow::Result<Url> {
+        let route = format!("/repos/{}/{}", self.owner, self.repository);
+        let (repository, _): (octocrab::models::Repository, _) = self.get(&route).await?;
+        let homepage = repository
+            .homepage
+            .context("You must set the homepage URL of your blog on the repository")?;
+        Url::parse(&homepage).map_err(Into::into)
+    }
+
+    /// The quota of requests left at the end of the build.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    /// Sends a conditional `GET` request, the response is served from the cache
+    /// when GitHub says it didn't change. The transient failures are retried.
+    /// Returns the deserialized body and the URL of the next page.
+    async fn get<T: DeserializeOwned>(&self, route: &str) -> anyhow::Result<(T, Option<String>)> {
+        if let Some((link, body)) = self.fixtures.replay(route)? {
+            let value = serde_json::from_slice(&body)
+                .with_context(|| format!("while deserializing the response of {route}"))?;
+            return Ok((value, link.as_deref().and_then(|l| self.next_page(l))));
+        }
+
+        let cached = self.cache.get(route);
+        let mut headers = HeaderMap::new();
+        if let Some((response, _)) = &cached {
+            for (name, value) in HttpCache::conditional_headers(response) {
+                headers.insert(name, HeaderValue::from_str(value)?);
+            }
+        }
+
+        let response = send_with_retries(route, &self.rate_limit, || {
+            self.octocrab._get_with_headers(route, Some(headers.clone()))
+        })
+        .await?;
+        let (response, body) = match cached {
+            Some((cached, body)) if response.status() == StatusCode::NOT_MODIFIED => (cached, body),
+            _ => {
+                let response = octocrab::map_github_error(response).await?;
+                let header = |name| {
+                    response.headers().get(name).and_then(|v| v.to_str().ok()).map(String::from)
+                };
+                let cached = CachedResponse {
+                    url: route.to_string(),
+                    etag: header(ETAG),
+                    last_modified: header(LAST_MODIFIED),
+                    link: header(LINK),
+                };
+                let body = self.octocrab.body_to_string(response).await?.into_bytes();
+                self.cache.insert(&cached, &body)?;
+                (cached, body)
+            }
+        };
+
+        self.fixtures.record(route, response.link.as_deref(), &body)?;
+        let value = serde_json::from_slice(&body)
+            .with_context(|| format!("while deserializing the response of {route}"))?;
+        Ok((value, response.link.as_deref().and_then(|l| self.next_page(l))))
+    }
+
+    /// The route of the next page, relative to the base URL of the API to
+    /// not repeat its path and to use the same cache key on any host.
+    fn next_page(&self, link: &str) -> Option<String> {
+        let next = next_page(link)?;
+        let base = self.api_url.as_str().trim_end_matches('/');
+        Some(next.strip_prefix(base).map_or(next.clone(), String::from))
+    }
+
+    /// Fetches all the pages of a paginated route.
+    async fn get_all_pages<T: DeserializeOwned>(&self, route: &str) -> anyhow::Result<Vec<T>> {
+        let (mut items, mut next): (Vec<T>, _) = self.get(route).await?;
+        while let Some(route) = next {
+            let (page, next_page): (Vec<T>, _) = self.get(&route).await?;
+            items.extend(page);
+            next = next_page;
+        }
+        Ok(items)
+    }
+}
+
+impl ArticleSource for GitHubSource {
+    async fn articles(&self) -> anyhow::Result<Vec<Article>> {
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("state", "open")
+            .append_pair("labels", &self.label)
+            .append_pair("per_page", &self.per_page.to_string())
+            .finish();
+        let route = format!("/repos/{}/{}/issues?{query}", self.owner, self.repository);
+        let issues: Vec<Issue> = self.get_all_pages(&route).await?;
+        info!("Fetched {} issues labelled `{}`", issues.len(), self.label);
+
+        let mut articles = Vec::new();
+        let mut warnings = Vec::new();
+        for issue in issues {
+            // The issues endpoint also returns the pull requests
+            if issue.pull_request.is_some() {
+                warnings.push(format!("#{} is a pull request, skipping it", issue.number));
+                continue;
+            }
+
+            // We render the body ourselves when GitHub doesn't, without
+            // the raw HTML that GitHub would have sanitized
+            let body_html = match issue.body_html {
+                Some(html) => Some(html),
+                None => {
+                    let number = issue.number;
+                    warnings.push(format!("#{number} has no `body_html`, rendering it locally"));
+                    issue.body.as_deref().map(crate::markdown::to_safe_html)
+                }
+            };
+
+            articles.push(Article {
+                number: Some(issue.number),
+                title: issue.title,
+                slug: None,
+                author: issue.user.login,
+                labels: issue.labels.into_iter().map(|l| l.name).collect(),
+                synopsis: None,
+                toc: None,
+                metadata: Metadata::from_body(issue.body.as_deref().unwrap_or_default())
+                    .unwrap_or_default(),
+                body: issue.body.unwrap_or_default(),
+                body_html,
+                created_at: issue.created_at,
+                updated_at: issue.updated_at,
+                comments_count: Some(issue.comments),
+            });
+        }
+
+        self.warnings.lock().unwrap().extend(warnings);
+        Ok(articles)
+    }
+
+    async fn author(&self, login: &str) -> anyhow::Result<Author> {
+        let (user, _): (User, _) = self.get(&format!("/users/{login}")).await?;
+        Ok(Author {
+            login: login.to_string(),
+            name: user.name,
+            bio: user.bio,
+            avatar_url: user.avatar_url,
+        })
+    }
+
+    async fn history(&self, article: &Article) -> anyhow::Result<ArticleHistory> {
+        let number = article.issue()?;
+        let route = format!(
+            "/repos/{}/{}/issues/{number}/timeline?per_page=100",
+            self.owner, self.repository
+        );
+        let events: Vec<TimelineEvent> = self.get_all_pages(&route).await?;
+        debug!("Fetched {} timeline events for #{number}", events.len());
+
+        let mut history = ArticleHistory::default();
+        for event in events {
+            if let Some(from_title) = event.rename.and_then(extract_from_field_from_rename) {
+                history.previous_titles.push(from_title);
+            }
+            if event.label.map_or(false, |e| e.name == self.label) {
+                history.publish_date = event.created_at;
+            }
+        }
+
+        Ok(history)
+    }
+
+    async fn reactions(&self, article: &Article) -> anyhow::Result<Option<ReactionCounts>> {
+        let mut output = ReactionCounts::default();
+
+        let number = article.issue()?;
+        let route = format!(
+            "/repos/{}/{}/issues/{number}/reactions?per_page=100",
+            self.owner, self.repository
+        );
+        let reactions: Vec<Reaction> = self.get_all_pages(&route).await?;
+        debug!("Fetched {} reactions for #{number}", reactions.len());
+
+        for reaction in reactions {
+            match reaction.content {
+                ReactionContent::Heart => output.heart += 1,
+                ReactionContent::PlusOne => output.plus_one += 1,
+                ReactionContent::Laugh => output.laugh += 1,
+                ReactionContent::Confused => output.confused += 1,
+                ReactionContent::Hooray => output.hooray += 1,
+                ReactionContent::MinusOne => output.minus_one += 1,
+                ReactionContent::Rocket => output.rocket += 1,
+                ReactionContent::Eyes => output.eyes += 1,
+            }
+        }
+
+        Ok(Some(output))
+    }
+
+    fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut *self.warnings.lock().unwrap())
+    }
+}
+
+#[derive(Deserialize)]
+struct User {
+    avatar_url: Url,
+    name: String,
+    bio: String,
+}
+
+/// Creates the GitHub client, authenticated when a token is configured.
+pub(crate) fn build_octocrab(config: &Config) -> anyhow::Result<Octocrab> {
+    // force GitHub to return HTML content
+    let mut builder = OctocrabBuilder::default()
+        .base_uri(config.api_url.as_str())?
+        .add_header(ACCEPT, format_media_type("full"));
+    // We retry the requests ourselves, honoring the rate limits
+    builder.add_retry_config(RetryConfig::None);
+
+    let token = config.github_token.clone();
+    let octocrab = if let Some(token) = token.filter(|s| !s.is_empty()) {
+        info!("Sending authenticated requests to {}", config.api_url);
+        builder.personal_token(token).build()?
+    } else {
+        info!("Sending unauthenticated requests to {}", config.api_url);
+        builder.build()?
+    };
+
+    Ok(octocrab)
+}
+
+/// Sends a request until it succeeds or fails for a non-transient reason,
+/// and keeps track of the rate limit. The `send` function is called for every attempt.
+#[tracing::instrument(level = "debug", skip(rate_limit, send))]
+pub(crate) async fn send_with_retries<B, F>(
+    what: &str,
+    rate_limit: &Mutex<Option<RateLimit>>,
+    mut send: impl FnMut() -> F,
+) -> anyhow::Result<http::Response<B>>
+where
+    F: Future<Output = octocrab::Result<http::Response<B>>>,
+{
+    let mut attempt = 0;
+    loop {
+        let result = send().await;
+        let (reason, delay) = match &result {
+            Ok(response) => {
+                let header = |name: &str| {
+                    response.headers().get(name).and_then(|v| v.to_str().ok()).map(String::from)
+                };
+                if let Some(limit) = RateLimit::from_headers(header) {
+                    *rate_limit.lock().unwrap() = Some(limit);
+                }
+                let status = response.status();
+                (status.to_string(), retry_delay(attempt, Some(status.as_u16()), header))
+            }
+            Err(e) => (e.to_string(), retry_delay(attempt, None, |_| None)),
+        };
+
+        match delay {
+            Some(delay) => {
+                warn!("{what} failed ({reason}), retrying in {delay:?}");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            None => return result.map_err(Into::into),
+        }
+    }
+}
+
+/// Extracts the URL of the next page from a `Link` header, formatted like
+/// `<https://api.github.com/...?page=2>; rel="next", <...>; rel="last"`.
+fn next_page(link: &str) -> Option<String> {
+    link.split(',').find_map(|link| {
+        let (url, rel) = link.split_once(';')?;
+        let url = url.trim().strip_prefix('<')?.strip_suffix('>')?;
+        (rel.trim() == r#"rel="next""#).then(|| url.to_string())
+    })
+}
+
+/// Because the Rename struct only has private field we are
+/// forced to serialize/deserialize-trick to extract the from field, for now.
+fn extract_from_field_from_rename(rename: Rename) -> Option<String> {
+    match serde_json::to_value(rename).unwrap()["from"] {
+        serde_json::Value::String(ref s) => Some(s.clone()),
+        _ => None,
+    }
+}
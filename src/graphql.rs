@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
+use octocrab::Octocrab;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{debug, info};
+use url::Url;
+
+use crate::config::Config;
+use crate::fixtures::Fixtures;
+use crate::github::{build_octocrab, send_with_retries};
+use crate::metadata::Metadata;
+use crate::retry::RateLimit;
+use crate::source::{Article, ArticleHistory, ArticleSource, Author, ReactionCounts};
+
+/// Fetches the issues with their labels, comment and reaction counts, authors,
+/// renames and labelling events, a page of issues at a time.
+const ARTICLES_QUERY: &str = r#"
+query($owner: String!, $name: String!, $label: String!, $perPage: Int!, $after: String) {
+  repository(owner: $owner, name: $name) {
+    issues(first: $perPage, after: $after, labels: [$label], states: OPEN,
+           orderBy: { field: CREATED_AT, direction: DESC }) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        number title body bodyHTML createdAt updatedAt
+        author { login avatarUrl ... on User { name bio } }
+        labels(first: 100) { nodes { name } }
+        comments { totalCount }
+        reactionGroups { content reactors { totalCount } }
+        timelineItems(last: 100, itemTypes: [RENAMED_TITLE_EVENT, LABELED_EVENT]) {
+          pageInfo { hasPreviousPage }
+          nodes {
+            __typename
+            ... on RenamedTitleEvent { previousTitle }
+            ... on LabeledEvent { createdAt label { name } }
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+/// Fetches a user or an organization, the owner of a blog can be either.
+const OWNER_QUERY: &str = r#"
+query($login: String!) {
+  repositoryOwner(login: $login) {
+    login avatarUrl
+    ... on User { name bio }
+    ... on Organization { name bio: description }
+  }
+}
+"#;
+
+const HOMEPAGE_QUERY: &str = r#"
+query($owner: String!, $name: String!) {
+  repository(owner: $owner, name: $name) { homepageUrl }
+}
+"#;
+
+/// Fetches the articles from the issues of a GitHub repository with the GraphQL API.
+///
+/// Everything the build needs is fetched with the articles, in a handful of
+/// paginated queries, instead of a few requests per article with the REST API.
+/// The GraphQL API requires a token.
+pub struct GraphQlSource {
+    octocrab: Octocrab,
+    owner: String,
+    repository: String,
+    label: String,
+    per_page: u8,
+    /// The history and reactions of the articles, fetched with the articles.
+    fetched: Mutex<HashMap<u64, (ArticleHistory, ReactionCounts)>>,
+    /// The authors of the articles, fetched with the articles.
+    authors: Mutex<HashMap<String, Author>>,
+    fixtures: Fixtures,
+    rate_limit: Mutex<Option<RateLimit>>,
+    /// The issues degraded while listing the articles.
+    warnings: Mutex<Vec<String>>,
+}
+
+impl GraphQlSource {
+    pub fn new(
+        owner: impl Into<String>,
+        repository: impl Into<String>,
+        config: &Config,
+    ) -> anyhow::Result<GraphQlSource> {
+        let has_token = config.github_token.as_ref().map_or(false, |t| !t.is_empty());
+        if !has_token && !config.fixtures.is_replaying() {
+            bail!(
+                "the GraphQL API requires the `GITHUB_TOKEN` env var or the `github_token` config"
+            );
+        }
+
+        Ok(GraphQlSource {
+            octocrab: build_octocrab(config)?,
+            owner: owner.into(),
+            repository: repository.into(),
+            label: config.label.clone(),
+            per_page: config.per_page,
+            fetched: Mutex::new(HashMap::new()),
+            authors: Mutex::new(HashMap::new()),
+            fixtures: config.fixtures.clone(),
+            rate_limit: Mutex::new(None),
+            warnings: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// The homepage URL of the blog defined on the repository.
+    pub async fn homepage(&self) -> anyhow::Result<Url> {
+        let variables = json!({ "owner": self.owner, "name": self.repository });
+        let data: HomepageData = self.query(HOMEPAGE_QUERY, variables).await?;
+        let homepage = data
+            .repository
+            .homepage_url
+            .context("You must set the homepage URL of your blog on the repository")?;
+        Url::parse(&homepage).map_err(Into::into)
+    }
+
+    /// The quota of requests left at the end of the build.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    /// Sends a query and returns its data, the transient failures are retried.
+    async fn query<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> anyhow::Result<T> {
+        let payload = json!({ "query": query, "variables": variables });
+        // The query and its variables identify the recorded responses
+        let request = format!("POST /graphql {payload}");
+        let body = match self.fixtures.replay(&request)? {
+            Some((_, body)) => String::from_utf8(body)?,
+            None => {
+                let response = send_with_retries("/graphql", &self.rate_limit, || {
+                    self.octocrab._post("/graphql", Some(&payload))
+                })
+                .await?;
+                let response = octocrab::map_github_error(response).await?;
+                let body = self.octocrab.body_to_string(response).await?;
+                self.fixtures.record(&request, None, body.as_bytes())?;
+                body
+            }
+        };
+
+        let response: GraphQlResponse<T> = serde_json::from_str(&body)
+            .context("while deserializing the response of the GraphQL API")?;
+        match (response.data, response.errors) {
+            (Some(data), None) => Ok(data),
+            (_, Some(errors)) if !errors.is_empty() => {
+                let messages: Vec<_> = errors.into_iter().map(|e| e.message).collect();
+                bail!("the GraphQL API returned errors: {}", messages.join(", "))
+            }
+            _ => bail!("the GraphQL API returned no data"),
+        }
+    }
+}
+
+impl ArticleSource for GraphQlSource {
+    async fn articles(&self) -> anyhow::Result<Vec<Article>> {
+        let mut issues = Vec::new();
+        let mut after = None;
+        loop {
+            let variables = json!({
+                "owner": self.owner,
+                "name": self.repository,
+                "label": self.label,
+                "perPage": self.per_page,
+                "after": after,
+            });
+            let data: ArticlesData = self.query(ARTICLES_QUERY, variables).await?;
+            let IssueConnection { page_info, nodes } = data.repository.issues;
+            issues.extend(nodes);
+            match page_info.end_cursor.filter(|_| page_info.has_next_page) {
+                Some(cursor) => after = Some(cursor),
+                None => break,
+            }
+        }
+        info!("Fetched {} issues labelled `{}`", issues.len(), self.label);
+
+        let mut articles = Vec::new();
+        let mut warnings = Vec::new();
+        let mut fetched = self.fetched.lock().unwrap();
+        let mut authors = self.authors.lock().unwrap();
+        for issue in issues {
+            if issue.timeline_items.page_info.has_previous_page {
+                warnings.push(format!(
+                    "#{} has too many renames and labels, the oldest ones are ignored",
+                    issue.number
+                ));
+            }
+
+            let mut history = ArticleHistory::default();
+            for item in issue.timeline_items.nodes {
+                match item {
+                    TimelineItem::RenamedTitleEvent { previous_title } => {
+                        history.previous_titles.push(previous_title)
+                    }
+                    TimelineItem::LabeledEvent { created_at, label }
+                        if label.name == self.label =>
+                    {
+                        history.publish_date = Some(created_at)
+                    }
+                    _ => (),
+                }
+            }
+
+            let mut reactions = ReactionCounts::default();
+            for group in issue.reaction_groups {
+                let count = group.reactors.total_count;
+                match group.content.as_str() {
+                    "HEART" => reactions.heart = count,
+                    "THUMBS_UP" => reactions.plus_one = count,
+                    "LAUGH" => reactions.laugh = count,
+                    "CONFUSED" => reactions.confused = count,
+                    "HOORAY" => reactions.hooray = count,
+                    "THUMBS_DOWN" => reactions.minus_one = count,
+                    "ROCKET" => reactions.rocket = count,
+                    "EYES" => reactions.eyes = count,
+                    _ => (),
+                }
+            }
+
+            // The author is missing when the account has been deleted
+            let author = issue.author.map(Author::from).unwrap_or_else(|| Author {
+                login: "ghost".to_string(),
+                name: "Deleted user".to_string(),
+                bio: String::new(),
+                avatar_url: Url::parse("https://github.com/ghost.png").unwrap(),
+            });
+
+            fetched.insert(issue.number, (history, reactions));
+            articles.push(Article {
+                number: Some(issue.number),
+                title: issue.title,
+                slug: None,
+                author: author.login.clone(),
+                labels: issue.labels.nodes.into_iter().map(|l| l.name).collect(),
+                synopsis: None,
+                toc: None,
+                metadata: Metadata::from_body(&issue.body).unwrap_or_default(),
+                body: issue.body,
+                body_html: Some(issue.body_html),
+                created_at: issue.created_at,
+                updated_at: issue.updated_at,
+                comments_count: Some(issue.comments.total_count as u32),
+            });
+            authors.insert(author.login.clone(), author);
+        }
+
+        self.warnings.lock().unwrap().extend(warnings);
+        Ok(articles)
+    }
+
+    async fn author(&self, login: &str) -> anyhow::Result<Author> {
+        if let Some(author) = self.authors.lock().unwrap().get(login) {
+            return Ok(author.clone());
+        }
+
+        // The owner of the blog isn't always the author of an article
+        let data: OwnerData = self.query(OWNER_QUERY, json!({ "login": login })).await?;
+        let owner = data
+            .repository_owner
+            .with_context(|| format!("unknown user or organization {login}"))?;
+        let author = Author::from(owner);
+        self.authors.lock().unwrap().insert(login.to_string(), author.clone());
+        Ok(author)
+    }
+
+    async fn history(&self, article: &Article) -> anyhow::Result<ArticleHistory> {
+        let number = article.issue()?;
+        let fetched = self.fetched.lock().unwrap();
+        let (history, _) =
+            fetched.get(&number).with_context(|| format!("#{number} has not been fetched"))?;
+        debug!("Found {} renames for #{number}", history.previous_titles.len());
+        Ok(history.clone())
+    }
+
+    async fn reactions(&self, article: &Article) -> anyhow::Result<Option<ReactionCounts>> {
+        let number = article.issue()?;
+        let fetched = self.fetched.lock().unwrap();
+        let (_, reactions) =
+            fetched.get(&number).with_context(|| format!("#{number} has not been fetched"))?;
+        Ok(Some(reactions.clone()))
+    }
+
+    fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut *self.warnings.lock().unwrap())
+    }
+}
+
+#[derive(Deserialize)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQlError>>,
+}
+
+#[derive(Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct HomepageData {
+    repository: HomepageRepository,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HomepageRepository {
+    homepage_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OwnerData {
+    repository_owner: Option<User>,
+}
+
+#[derive(Deserialize)]
+struct ArticlesData {
+    repository: ArticlesRepository,
+}
+
+#[derive(Deserialize)]
+struct ArticlesRepository {
+    issues: IssueConnection,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IssueConnection {
+    page_info: PageInfo,
+    nodes: Vec<Issue>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Issue {
+    number: u64,
+    title: String,
+    body: String,
+    #[serde(rename = "bodyHTML")]
+    body_html: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    author: Option<User>,
+    labels: Nodes<Label>,
+    comments: TotalCount,
+    reaction_groups: Vec<ReactionGroup>,
+    timeline_items: TimelineItems,
+}
+
+/// An actor of the GitHub API, the `name` and `bio` are only defined for users
+/// and organizations, the bio of an organization is its description.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct User {
+    login: String,
+    avatar_url: Url,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    bio: Option<String>,
+}
+
+impl From<User> for Author {
+    fn from(user: User) -> Author {
+        Author {
+            name: user.name.filter(|n| !n.is_empty()).unwrap_or_else(|| user.login.clone()),
+            bio: user.bio.unwrap_or_default(),
+            login: user.login,
+            avatar_url: user.avatar_url,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Nodes<T> {
+    nodes: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct Label {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TotalCount {
+    total_count: usize,
+}
+
+#[derive(Deserialize)]
+struct ReactionGroup {
+    content: String,
+    reactors: TotalCount,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TimelineItems {
+    page_info: TimelinePageInfo,
+    nodes: Vec<TimelineItem>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TimelinePageInfo {
+    has_previous_page: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "__typename")]
+enum TimelineItem {
+    #[serde(rename_all = "camelCase")]
+    RenamedTitleEvent { previous_title: String },
+    #[serde(rename_all = "camelCase")]
+    LabeledEvent { created_at: DateTime<Utc>, label: Label },
+    #[serde(other)]
+    Other,
+}
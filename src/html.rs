@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::metadata::is_metadata_comment;
+
+const GITHUB_BASE_URL: &str = "https://github.com/";
+
+pub fn linkify_at_references(bio: impl AsRef<str>) -> String {
+    regex::Regex::new(r"(@(\w+))")
+        .unwrap()
+        .replace_all(bio.as_ref(), format!("<a href=\"{GITHUB_BASE_URL}$2\">$1</a>"))
+        .into_owned()
+}
+
+/// Points the linked images to the local copies they are downloaded into. Returns the
+/// URLs of the images to download with their local path, and the rewritten HTML.
+/// The images without a source, or with an empty one, are left untouched.
+pub fn replace_img_srcs_with_hashes(
+    html: impl AsRef<str>,
+) -> anyhow::Result<(HashMap<String, PathBuf>, String)> {
+    use kuchiki::parse_html;
+    use kuchiki::traits::*;
+
+    let mut urls_to_local_path = HashMap::new();
+    let document = parse_html().one(html.as_ref());
+
+    let images = document.select("a > img").map_err(|()| anyhow!("invalid selector"))?;
+    for img_element in images.collect::<Vec<_>>() {
+        let Some(a_node) = img_element.as_node().parent() else { continue };
+        let Some(a_element) = a_node.as_element() else { continue };
+        let attributes = img_element.attributes.borrow();
+        let Some(img_src) = attributes.get("src").filter(|src| !src.is_empty()).map(String::from)
+        else {
+            continue;
+        };
+        drop(attributes);
+
+        let local_path = hash_path_from_url(&img_src)?;
+        urls_to_local_path.insert(img_src, local_path.clone());
+        a_element.attributes.borrow_mut().insert("href", local_path.display().to_string());
+        img_element.attributes.borrow_mut().insert("src", local_path.display().to_string());
+    }
+
+    Ok((urls_to_local_path, document.to_string()))
+}
+
+fn hash_path_from_url(url: &str) -> anyhow::Result<PathBuf> {
+    let hasher = BuildHasherDefault::<DefaultHasher>::default();
+    let hash = hasher.hash_one(url);
+    let parsed = Url::parse(url).with_context(|| format!("invalid image source {url:?}"))?;
+    let path = PathBuf::new().join("assets").join("images").join(format!("{hash:x}"));
+    match Path::new(parsed.path()).extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => Ok(path.with_extension(extension)),
+        None => Ok(path.with_extension("png")),
+    }
+}
+
+pub fn synopsis(s: impl AsRef<str>) -> String {
+    let html = scraper::Html::parse_fragment(s.as_ref());
+    fn get_first_html_comment(document: &Html) -> Option<&str> {
+        for node in document.tree.nodes() {
+            match node.value().as_comment() {
+                Some(comment) if !is_metadata_comment(comment) => return Some(comment),
+                _ => (),
+            }
+        }
+        None
+    }
+
+    get_first_html_comment(&html).map_or_else(String::new, ToOwned::to_owned)
+}
+
+/// The plain text of the first paragraph of the HTML, used as the synopsis of the
+/// articles without one. It is cut after the last sentence that fits in `max_length`
+/// characters or, when the first sentence is already too long, after the last word.
+pub fn first_paragraph(html: impl AsRef<str>, max_length: usize) -> String {
+    let html = Html::parse_fragment(html.as_ref());
+    let selector = Selector::parse("p").unwrap();
+    let text = html
+        .select(&selector)
+        .map(|p| p.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" "))
+        .find(|text| !text.is_empty())
+        .unwrap_or_default();
+
+    if text.chars().count() <= max_length {
+        return text;
+    }
+
+    let end = text.char_indices().nth(max_length).map_or(text.len(), |(i, _)| i);
+    let sentence_end = text[..end]
+        .char_indices()
+        .filter(|&(i, c)| matches!(c, '.' | '!' | '?') && text[i + 1..].starts_with(' '))
+        .last();
+
+    match sentence_end {
+        Some((i, _)) => text[..=i].to_string(),
+        None => {
+            let words = text[..end].rfind(' ').map_or(&text[..end], |i| &text[..i]);
+            format!("{}…", words.trim_end_matches(|c: char| c.is_ascii_punctuation()))
+        }
+    }
+}
+
+/// Computes the id GitHub gives to a heading from its text content, without the
+/// `user-content-` prefix: lowercased, with the punctuation removed and the spaces
+/// replaced by dashes. The `#slug` links written in the issues keep working.
+pub fn github_slug(text: &str) -> String {
+    text.trim()
+        .chars()
+        .filter_map(|c| match c {
+            c if c.is_whitespace() => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Escapes the text to insert it into HTML, in an element or a quoted attribute.
+pub fn escape(s: &str) -> String {
+    let mut output = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+pub fn correct_dash_case(s: impl AsRef<str>) -> String {
+    use slice_group_by::StrGroupBy;
+
+    let mut output = String::new();
+    for group in s.as_ref().linear_group_by_key(|x| x.is_ascii_alphanumeric()) {
+        if let Some(x) = group.chars().next() {
+            if x.is_alphanumeric() {
+                output.extend(group.chars().map(|x| x.to_ascii_lowercase()));
+            } else {
+                output.push('-');
+            }
+        }
+    }
+
+    if output.ends_with('-') {
+        output.pop();
+    }
+
+    output
+}
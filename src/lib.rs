@@ -4,21 +4,63 @@ use anyhow::Context;
 use askama::Template;
 use resvg::render;
 use tiny_skia::{Pixmap, Transform};
+use tracing::warn;
 use unicode_segmentation::UnicodeSegmentation;
 use usvg::{ImageHrefResolver, ImageKind, Options, Tree};
 
+use crate::cache::HttpCache;
+pub use crate::config::{Api, Config, ErrorPolicy, PreviewImages, CONFIG_FILE_NAME};
+pub use crate::fixtures::Fixtures;
+pub use crate::github::GitHubSource;
+pub use crate::graphql::GraphQlSource;
+pub use crate::local::{export, LocalSource};
+pub use crate::metadata::Metadata;
+pub use crate::pipeline::{build, check, Site};
+pub use crate::report::{ArticleError, BuildReport};
+pub use crate::retry::RateLimit;
+pub use crate::serve::serve;
+pub use crate::source::{Article, ArticleHistory, ArticleSource, Author, ReactionCounts, Source};
+pub use crate::templates::{ArticleInList, ArticleTemplate, IndexTemplate, RedirectTemplate};
+pub use crate::toc::TableOfContents;
+pub use crate::transform::{HeadingAnchors, HtmlTransform, TableClasses, Transforms};
+pub use crate::watch::Watcher;
 use crate::Spans::*;
 
+mod cache;
+mod config;
+mod fixtures;
+mod github;
+mod graphql;
+mod html;
+mod local;
+mod manifest;
+mod markdown;
+mod metadata;
+mod output;
+mod pipeline;
+mod report;
+mod retry;
+mod serve;
+mod source;
+mod templates;
+mod toc;
+mod transform;
+mod watch;
+
 pub const WIDTH: u32 = 1200;
 pub const HEIGHT: u32 = 630;
 
+/// The bytes every PNG image starts with.
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
 #[derive(Template)]
 #[template(path = "blog-post-preview.svg", escape = "none")]
 struct PreviewTemplate {
     username: String,
     publish_date: String,
     title_spans: Spans,
-    comments_text: String,
+    comments_text: Option<String>,
+    domain: String,
 }
 
 enum Spans {
@@ -31,22 +73,45 @@ pub struct Preview {
     pub username: String,
     pub publish_date: String,
     pub title: String,
-    pub comment_count: u32,
+    /// The number of comments, not drawn when unknown.
+    pub comment_count: Option<u32>,
+    /// The domain of the blog, where the keys images are fetched from.
+    pub domain: String,
 }
 
 impl Preview {
-    pub fn generate_png(self) -> anyhow::Result<Vec<u8>> {
-        let Preview { username, publish_date, title, comment_count } = self;
-
-        let comments_text = if comment_count == 1 {
-            format!("{comment_count} comment")
-        } else {
-            format!("{comment_count} comments")
+    /// The SVG the preview is rendered from, the title is cut into up to three lines.
+    pub fn svg(&self) -> String {
+        let Preview { username, publish_date, title, comment_count, domain } = self;
+
+        let comments_text = comment_count.map(|count| match count {
+            1 => format!("{count} comment"),
+            _ => format!("{count} comments"),
+        });
+
+        let template = PreviewTemplate {
+            username: username.clone(),
+            publish_date: publish_date.clone(),
+            title_spans: cut_title(title),
+            comments_text,
+            domain: domain.clone(),
         };
+        template.to_string()
+    }
+
+    /// Renders the preview with the images downloaded through the HTTP cache,
+    /// or replayed from the fixtures.
+    pub fn generate_png(self, config: &Config) -> anyhow::Result<Vec<u8>> {
+        self.generate_png_with(preview_images(config))
+    }
 
-        let title_spans = cut_title(&title);
-        let template = PreviewTemplate { username, publish_date, title_spans, comments_text };
-        let svg = template.to_string();
+    /// Renders the preview with the PNG images returned by `fetch_png` for
+    /// the URLs of the keys and the profile picture of the author.
+    pub fn generate_png_with(
+        self,
+        fetch_png: impl Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+    ) -> anyhow::Result<Vec<u8>> {
+        let svg = self.svg();
 
         // Create a new pixmap buffer to render to
         let mut pixmap = Pixmap::new(WIDTH, HEIGHT).context("Pixmap allocation error")?;
@@ -58,17 +123,7 @@ impl Preview {
             shape_rendering: usvg::ShapeRendering::CrispEdges,
             image_href_resolver: ImageHrefResolver {
                 resolve_string: Box::new(move |path: &str, _| {
-                    let response = ureq::get(path).call().ok()?;
-                    let content_type = response.header("content-type")?;
-                    match content_type {
-                        "image/png" => {
-                            let mut image_buffer = Vec::new();
-                            response.into_reader().read_to_end(&mut image_buffer).ok()?;
-                            Some(ImageKind::PNG(Arc::new(image_buffer)))
-                        }
-                        // ... excluding other content types
-                        _ => None,
-                    }
+                    fetch_png(path).map(|image| ImageKind::PNG(Arc::new(image)))
                 }),
                 ..Default::default()
             },
@@ -83,6 +138,38 @@ impl Preview {
     }
 }
 
+/// Fetches the images of the previews through the HTTP cache, with retries, and
+/// records or replays them like the other requests. The images that can't be
+/// fetched or aren't PNGs are logged and left blank on the preview.
+pub(crate) fn preview_images(
+    config: &Config,
+) -> impl Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static {
+    let cache = HttpCache::new(&config.cache);
+    let fixtures = config.fixtures.clone();
+    let images = config.preview_images.clone();
+    let fetch = move |url: &str| -> anyhow::Result<Vec<u8>> {
+        let image = match fixtures.replay(url)? {
+            Some((_, body)) => body,
+            None => cache.download(&images.resolve(url))?,
+        };
+        fixtures.record(url, None, &image)?;
+        Ok(image)
+    };
+
+    move |url| match fetch(url) {
+        Ok(image) if image.starts_with(PNG_SIGNATURE) => Some(image),
+        // ... excluding other content types
+        Ok(_) => {
+            warn!("{url} is not a PNG image, ignoring it");
+            None
+        }
+        Err(e) => {
+            warn!("while fetching {url}: {e:#}, ignoring it");
+            None
+        }
+    }
+}
+
 fn cut_title(title: &str) -> Spans {
     const MAX_LINE_CHARS: usize = 26;
 
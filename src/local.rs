@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use url::Url;
+
+use crate::html::replace_img_srcs_with_hashes;
+use crate::metadata::Metadata;
+use crate::source::{
+    validate_slug, Article, ArticleHistory, ArticleSource, Author, ReactionCounts,
+};
+
+/// The name of the optional file, in the posts directory, describing the authors.
+const AUTHORS_FILE_NAME: &str = "authors.toml";
+
+/// Fetches the articles from a directory of Markdown files
+/// starting with a YAML (`---`) or TOML (`+++`) front matter.
+/// Every file is published, the labels don't have to contain
+/// the one selecting the GitHub issues.
+///
+/// ```markdown
+/// ---
+/// title: My First Article
+/// date: 2024-03-10
+/// author: Kerollmops
+/// labels: [article, rust]
+/// synopsis: This will be used as the synopsis on the main page
+/// toc: true
+/// slug: my-first-article
+/// ---
+///
+/// This is the first sentence of my blog post.
+/// ```
+pub struct LocalSource {
+    directory: PathBuf,
+    default_author: String,
+    authors: HashMap<String, AuthorProfile>,
+}
+
+impl LocalSource {
+    /// Reads the articles from the given directory, articles without
+    /// an `author` field are considered written by the `default_author`.
+    pub fn new(
+        directory: impl Into<PathBuf>,
+        default_author: impl Into<String>,
+    ) -> anyhow::Result<LocalSource> {
+        let directory = directory.into();
+        let authors_path = directory.join(AUTHORS_FILE_NAME);
+        let authors = match std::fs::read_to_string(&authors_path) {
+            Ok(content) => toml::from_str(&content)
+                .with_context(|| format!("while parsing {}", authors_path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(LocalSource { directory, default_author: default_author.into(), authors })
+    }
+
+    async fn read_article(&self, path: &Path) -> anyhow::Result<Article> {
+        let content = fs::read_to_string(path).await?;
+        let modified = fs::metadata(path).await?.modified()?;
+        let (front_matter, body) = parse_front_matter(&content)?;
+        // The relative images are next to the Markdown file
+        let base = Url::from_file_path(fs::canonicalize(path).await?).ok();
+        let FrontMatter { title, date, author, labels, synopsis, toc, slug, issue } = front_matter;
+        if let Some(slug) = &slug {
+            validate_slug(slug)?;
+        }
+        let metadata = Metadata::from_body(body).unwrap_or_default();
+        let body_html = crate::markdown::to_html(body, base.as_ref());
+
+        // The article must be rendered again when one of its images is edited
+        let mut updated_at = DateTime::from(modified);
+        let (images, _) = replace_img_srcs_with_hashes(&body_html)?;
+        for url in images.keys() {
+            let Some(path) = Url::parse(url).ok().and_then(|url| url.to_file_path().ok()) else {
+                continue;
+            };
+            if let Ok(metadata) = fs::metadata(&path).await {
+                updated_at = updated_at.max(DateTime::from(metadata.modified()?));
+            }
+        }
+
+        Ok(Article {
+            number: issue,
+            title,
+            slug,
+            author: author.unwrap_or_else(|| self.default_author.clone()),
+            labels,
+            synopsis,
+            toc,
+            metadata,
+            body: body.to_string(),
+            body_html: Some(body_html),
+            created_at: parse_date(&date)?,
+            updated_at,
+            comments_count: None,
+        })
+    }
+}
+
+impl ArticleSource for LocalSource {
+    async fn articles(&self) -> anyhow::Result<Vec<Article>> {
+        let mut articles = Vec::new();
+        let mut entries = fs::read_dir(&self.directory)
+            .await
+            .with_context(|| format!("while reading {}", self.directory.display()))?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().map_or(false, |ext| ext == "md") {
+                let article = self
+                    .read_article(&path)
+                    .await
+                    .with_context(|| format!("while reading {}", path.display()))?;
+                articles.push(article);
+            }
+        }
+
+        // GitHub lists the most recent issues first
+        articles.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+        Ok(articles)
+    }
+
+    async fn author(&self, login: &str) -> anyhow::Result<Author> {
+        let profile = self.authors.get(login);
+        let avatar_url = match profile.and_then(|p| p.avatar_url.clone()) {
+            Some(url) => url,
+            None => Url::parse(&format!("https://github.com/{login}.png"))?,
+        };
+
+        Ok(Author {
+            login: login.to_string(),
+            name: profile.and_then(|p| p.name.clone()).unwrap_or_else(|| login.to_string()),
+            bio: profile.and_then(|p| p.bio.clone()).unwrap_or_default(),
+            avatar_url,
+        })
+    }
+
+    async fn history(&self, article: &Article) -> anyhow::Result<ArticleHistory> {
+        Ok(ArticleHistory { previous_titles: Vec::new(), publish_date: Some(article.created_at) })
+    }
+
+    // The comments and reactions of the linked issues are unknown, they are hidden
+    async fn reactions(&self, _article: &Article) -> anyhow::Result<Option<ReactionCounts>> {
+        Ok(None)
+    }
+
+    fn images_directory(&self) -> Option<&Path> {
+        Some(&self.directory)
+    }
+}
+
+/// Writes the articles of a source into a directory of Markdown
+/// files that can be read back by the [`LocalSource`].
+///
+/// Returns the number of exported articles.
+pub async fn export(source: &impl ArticleSource, directory: &Path) -> anyhow::Result<usize> {
+    fs::create_dir_all(directory).await?;
+
+    let mut count = 0;
+    for article in source.articles().await? {
+        let history = source.history(&article).await?;
+        let slug = article.slug();
+        let front_matter = FrontMatter {
+            title: article.title,
+            date: history.publish_date.unwrap_or(article.created_at).to_rfc3339(),
+            author: Some(article.author),
+            labels: article.labels,
+            synopsis: article.synopsis,
+            toc: article.toc,
+            slug: Some(slug.clone()),
+            issue: article.number,
+        };
+
+        let front_matter = serde_yaml::to_string(&front_matter)?;
+        let content = format!("---\n{front_matter}---\n\n{}\n", article.body.trim_end());
+        let path = directory.join(format!("{slug}.md"));
+        fs::write(&path, content)
+            .await
+            .with_context(|| format!("while writing {}", path.display()))?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[derive(Deserialize)]
+struct AuthorProfile {
+    name: Option<String>,
+    bio: Option<String>,
+    avatar_url: Option<Url>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct FrontMatter {
+    title: String,
+    /// Either a `YYYY-MM-DD` date or an RFC 3339 datetime.
+    date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    labels: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    synopsis: Option<String>,
+    /// Forces or disables the table of contents.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    toc: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slug: Option<String>,
+    /// The issue where the comments and reactions are left.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    issue: Option<u64>,
+}
+
+/// Splits the front matter from the body and parses it.
+fn parse_front_matter(content: &str) -> anyhow::Result<(FrontMatter, &str)> {
+    let delimiter = ["---", "+++"]
+        .into_iter()
+        .find(|d| content.starts_with(d))
+        .context("missing front matter, the file must start with `---` or `+++`")?;
+
+    let rest = content[delimiter.len()..].trim_start_matches([' ', '\t', '\r']);
+    let rest = rest.strip_prefix('\n').context("the front matter delimiter must be alone")?;
+    let end = rest
+        .find(&format!("\n{delimiter}"))
+        .with_context(|| format!("missing the closing `{delimiter}` of the front matter"))?;
+    let (front_matter, body) = (&rest[..end], &rest[end + 1 + delimiter.len()..]);
+    let body = body.trim_start_matches(['\r', '\n']);
+
+    let front_matter = if delimiter == "---" {
+        serde_yaml::from_str(front_matter)?
+    } else {
+        // TOML has its own date type that we convert into strings
+        let mut table: toml::Table = toml::from_str(front_matter)?;
+        for (_, value) in table.iter_mut() {
+            if let toml::Value::Datetime(datetime) = value {
+                *value = toml::Value::String(datetime.to_string());
+            }
+        }
+        table.try_into()?
+    };
+
+    Ok((front_matter, body))
+}
+
+pub(crate) fn parse_date(s: &str) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .with_context(|| format!("invalid date {s:?}, expected `YYYY-MM-DD`"))?;
+    Ok(Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN)))
+}
@@ -1,449 +1,315 @@
-use std::collections::HashMap;
-use std::env;
-use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
-use std::path::{Path, PathBuf};
-
-use anyhow::Context;
-use askama::Template;
-use big_s::S;
-use http::header::ACCEPT;
-use octocrab::issues::IssueHandler;
-use octocrab::models::reactions::ReactionContent;
-use octocrab::models::timelines::Rename;
-use octocrab::params::State;
-use octocrab::{format_media_type, OctocrabBuilder};
-use regex::Captures;
-use rss::extension::atom::{AtomExtension, Link};
-use rss::{Channel, Guid, Item};
-use scraper::Html;
-use serde::Deserialize;
-use tokio::fs::{self, File};
-use tokio::io::{self, ErrorKind};
-use url::Url;
-
-const GITHUB_BASE_URL: &str = "https://github.com/";
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context};
+use blog::{
+    Api, Config, ErrorPolicy, Fixtures, GitHubSource, GraphQlSource, LocalSource, Preview, Site,
+    Source, Watcher, CONFIG_FILE_NAME,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+/// The directory of the askama templates, relative to the crate root.
+const TEMPLATES_DIRECTORY: &str = "templates";
+
+/// A small tool that generate the static pages of my blog.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// The path to the configuration file.
+    #[arg(long, global = true, default_value = CONFIG_FILE_NAME)]
+    config: PathBuf,
+
+    /// Logs more information about what is going on and how long each phase takes.
+    #[arg(long, short, global = true)]
+    verbose: bool,
+
+    /// The format of the logs, the JSON logs always contain the duration of each phase.
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Stores the responses of every network request into this directory.
+    #[arg(long, global = true, value_name = "DIR", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Builds from the responses recorded into this directory, without any network access.
+    #[arg(long, global = true, value_name = "DIR")]
+    replay: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let owner_repo = std::env::var("GITHUB_REPOSITORY").expect("please define `GITHUB_REPOSITORY`");
-    let email_address = std::env::var("EMAIL_ADDRESS").expect("please define `EMAIL_ADDRESS`");
-    let (owner, repo) = owner_repo.split_once('/').unwrap();
+#[derive(Clone, Copy, ValueEnum)]
+enum LogFormat {
+    Text,
+    Json,
+}
 
-    fs::remove_dir_all("output").await.or_else(ignore_not_found)?;
-    fs::create_dir("output").await?;
-    fs::create_dir("output/assets").await?;
-    fs::create_dir("output/preview").await?;
-    fs::create_dir("output/assets/keys").await?;
+#[derive(Subcommand)]
+enum Command {
+    /// Generates the whole website, this is the default command.
+    Build(BuildArgs),
+    /// Generates the preview image of an article.
+    Preview {
+        username: String,
+        publish_date: String,
+        title: String,
+        comment_count: u32,
+        /// The domain displayed on the preview, defaults to the configured one.
+        #[arg(long)]
+        domain: Option<String>,
+        /// Where to write the PNG image.
+        #[arg(long, default_value = "preview.png")]
+        output: PathBuf,
+    },
+    /// Reports the problems of the articles without generating anything.
+    Check,
+    /// Generates the website, serves it on localhost and regenerates it on changes.
+    ///
+    /// The website is generated into a temporary directory unless `--output` is given.
+    Serve {
+        #[command(flatten)]
+        build: BuildArgs,
+        /// The port to listen on.
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// Writes the articles into a directory of Markdown files with front matter.
+    Export {
+        /// The directory to write the Markdown files into.
+        #[arg(long, default_value = "posts")]
+        output: PathBuf,
+    },
+}
 
-    // Copy the JS assets
-    fs::copy("assets/preview/homepage.png", "output/preview/homepage.png").await?;
-    fs::copy("assets/script.js", "output/assets/script.js").await?;
-    fs::copy("assets/script.js", "output/assets/script.js").await?;
-    fs::copy("assets/balls.js", "output/assets/balls.js").await?;
-    fs::copy("assets/matter.min.js", "output/assets/matter.min.js").await?;
-    fs::copy("assets/tiny-utterances.js", "output/assets/tiny-utterances.js").await?;
-    fs::copy("assets/style.css", "output/assets/style.css").await?;
-    fs::copy("assets/tiny-utterances.css", "output/assets/tiny-utterances.css").await?;
-    fs::copy("assets/bootstrap.min.css", "output/assets/bootstrap.min.css").await?;
-    fs::copy("assets/starry-night.css", "output/assets/starry-night.css").await?;
+#[derive(clap::Args)]
+struct BuildArgs {
+    /// The directory in which the website is generated, overrides the configured one.
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// Only generates the article with this slug.
+    #[arg(long)]
+    only: Option<String>,
+    /// Renders all the articles again, even the ones that didn't change.
+    #[arg(long)]
+    force: bool,
+}
 
-    // Copy the keys assets
-    for key in ('A'..='Z').chain('0'..='9') {
-        let src = format!("assets/keys/{key}.png");
-        let dst = format!("output/assets/keys/{key}.png");
-        fs::copy(src, dst).await?;
+impl BuildArgs {
+    fn apply(self, config: &mut Config) {
+        if let Some(output) = self.output {
+            config.output = output;
+        }
+        config.only = self.only;
+        config.force |= self.force;
     }
+}
 
-    // force GitHub to return HTML content
-    let octocrab = if let Some(token) = env::var("GITHUB_TOKEN").ok().filter(|s| !s.is_empty()) {
-        eprintln!("I am authenticated!");
-        OctocrabBuilder::default()
-            .personal_token(token)
-            .add_header(ACCEPT, format_media_type("full"))
-            .build()?
-    } else {
-        eprintln!("I am not authenticated!");
-        OctocrabBuilder::default().add_header(ACCEPT, format_media_type("full")).build()?
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    init_logging(cli.verbose, cli.log_format);
+    let mut config = Config::load(&cli.config)?;
+    config.fixtures = match (cli.record, cli.replay) {
+        (Some(directory), _) => Fixtures::Record(directory),
+        (None, Some(directory)) => Fixtures::Replay(directory),
+        (None, None) => Fixtures::Off,
     };
-
-    let user: User = octocrab::instance().get(format!("/users/{}", owner), None::<&()>).await?;
-    let html_bio_owner = linkify_at_references(user.bio);
-
-    let repository = octocrab::instance().repos(owner, repo).get().await?;
-    let homepage = repository
-        .homepage
-        .context("You must set the homepage URL of your blog on the repository")?;
-    let homepage_url = Url::parse(&homepage)?;
-
-    let page = octocrab
-        .issues(owner, repo)
-        .list()
-        .state(State::Open)
-        .labels(&[S("article")])
-        .per_page(50)
-        .send()
-        .await?;
-
-    let mut items = Vec::new();
-    let mut articles = Vec::new();
-    for mut issue in page {
-        let falback_date = issue.created_at;
-        let body = issue.body.as_ref().unwrap();
-        let issue_handler = octocrab.issues(owner, repo);
-        let url = correct_dash_case(&issue.title);
-        let synopsis = synopsis(body);
-
-        if let Some(html) = issue.body_html {
-            let (urls_to_path, html) = replace_img_srcs_with_hashes(html);
-            issue.body_html = Some(html);
-
-            let mut body_bytes = Vec::new();
-            std::fs::create_dir_all("output/assets/images")?;
-            for (url, path) in urls_to_path {
-                body_bytes.clear();
-                let resp = ureq::get(&url).call()?;
-                resp.into_reader().read_to_end(&mut body_bytes)?;
-                std::fs::write(Path::new("output").join(path), &body_bytes)?;
+    // Every article must be rendered again to record or replay all the requests
+    config.force = !matches!(config.fixtures, Fixtures::Off);
+
+    match cli.command.unwrap_or(Command::Build(BuildArgs {
+        output: None,
+        only: None,
+        force: false,
+    })) {
+        Command::Build(args) => {
+            args.apply(&mut config);
+            let (source, site) = open_site(&config).await?;
+            let report = blog::build(&source, &site, &config).await?;
+            log_rate_limit(&source);
+            report.finish(&config)
+        }
+        Command::Preview { username, publish_date, title, comment_count, domain, output } => {
+            let domain = domain
+                .or(config.preview_domain.clone())
+                .context("please define the `--domain` argument or `preview_domain` config")?;
+            let comment_count = Some(comment_count);
+            let preview = Preview { username, publish_date, title, comment_count, domain };
+            std::fs::write(&output, preview.generate_png(&config)?)
+                .with_context(|| format!("while writing {}", output.display()))
+        }
+        Command::Check => {
+            let (source, _site) = open_source(&config).await?;
+            let problems = blog::check(&source).await?;
+            for problem in &problems {
+                warn!("{problem}");
+            }
+            match problems.len() {
+                0 => Ok(()),
+                count => bail!("found {count} problem(s)"),
             }
         }
-
-        // But we must also create the redirection HTML pages to redirect
-        // from the previous names of the article.
-        let events = issue_handler.list_timeline_events(issue.number).per_page(100).send().await?;
-
-        let mut publish_date = None;
-        for event in events {
-            if let Some(from_title) = event.rename.and_then(extract_from_field_from_rename) {
-                create_and_write_template_into(
-                    format!("output/{}.html", correct_dash_case(from_title)),
-                    RedirectTemplate { redirect_url: correct_dash_case(&issue.title) },
-                )
-                .await?;
+        Command::Serve { build, port } => {
+            if build.output.is_none() {
+                config.output = std::env::temp_dir().join(format!("blog-serve-{port}"));
             }
-            if event.label.map_or(false, |e| e.name == "article") {
-                publish_date = event.created_at;
+            // The website is served even if some articles fail to render
+            config.on_article_error = ErrorPolicy::KeepPrevious;
+            build.apply(&mut config);
+            let (source, site) = open_site(&config).await?;
+            serve_build(&source, &site, &config).await;
+
+            let (reloads, _) = broadcast::channel(16);
+            blog::serve(config.output.clone(), port, reloads.clone()).await?;
+
+            let mut watched = vec![PathBuf::from(TEMPLATES_DIRECTORY), PathBuf::from("assets")];
+            watched.extend(config.posts_directory.clone());
+            let mut watcher = Watcher::new(watched);
+            loop {
+                let changed = watcher.changed().await;
+                if changed.iter().any(|path| path.starts_with(TEMPLATES_DIRECTORY)) {
+                    recompile_and_restart()?;
+                }
+                info!("Rebuilding, {} file(s) changed...", changed.len());
+                serve_build(&source, &site, &config).await;
+                let _ = reloads.send(());
             }
         }
-
-        articles.push(ArticleInList {
-            title: issue.title.clone(),
-            synopsis: synopsis.clone(),
-            url: url.clone(),
-            publish_date: publish_date.unwrap_or(falback_date).format("%B %d, %Y").to_string(),
-            comments_count: issue.comments,
-            guest_user: Some(issue.user.login.clone()).filter(|u| !u.eq_ignore_ascii_case(owner)),
-        });
-
-        // Everytime we fetch an article we also fetch the author real name
-        let author: User =
-            octocrab::instance().get(format!("/users/{}", issue.user.login), None::<&()>).await?;
-        let html_bio = linkify_at_references(author.bio);
-
-        let mut profil_picture_url = author.avatar_url;
-        profil_picture_url.set_query(Some("v=4&s=100"));
-        let reaction_counts = collect_reactions(&issue_handler, issue.number).await?;
-
-        items.push(Item {
-            guid: Some(Guid { value: homepage_url.join(&url)?.to_string(), permalink: true }),
-            title: Some(issue.title.clone()),
-            link: Some(homepage_url.join(&url)?.to_string()),
-            description: Some(synopsis.clone()),
-            author: Some(format!("{email_address} ({})", author.name)),
-            atom_ext: Some(AtomExtension {
-                links: vec![Link {
-                    rel: "related".into(),
-                    href: homepage_url.join(&url)?.to_string(),
-                    title: Some(issue.title.clone()),
-                    ..Default::default()
-                }],
-            }),
-            pub_date: Some(publish_date.as_ref().unwrap_or(&falback_date).to_rfc2822()),
-            ..Default::default()
-        });
-
-        // We create the article HTML pages. We must do that after the redirection
-        // pages to be sure to replace the final HTML page by the article.
-        let post_dash_case = correct_dash_case(&issue.title);
-        create_and_write_template_into(
-            format!("output/{post_dash_case}.html"),
-            ArticleTemplate {
-                profil_picture_url,
-                username: author.name.clone(),
-                html_bio: html_bio.clone(),
-                url: format!("{homepage}{post_dash_case}"),
-                publish_date: publish_date.unwrap_or(falback_date).format("%B %d, %Y").to_string(),
-                title: issue.title.clone(),
-                description: synopsis,
-                html_content: insert_table_class_to_table(insert_anchor_to_headers(
-                    issue.body_html.unwrap(),
-                )),
-                comments_count: issue.comments,
-                reaction_counts,
-                owner: owner.to_string(),
-                repository: repo.to_string(),
-                issue_number: issue.number,
-                preview_url: format!("{homepage}preview/{post_dash_case}.png"),
-            },
-        )
-        .await?;
-
-        // Generate the preview
-        let preview_png = tokio::task::block_in_place(|| {
-            let preview = blog::Preview {
-                username: issue.user.login,
-                publish_date: publish_date.unwrap_or(falback_date).format("%B %d, %Y").to_string(),
-                title: issue.title.clone(),
-                comment_count: issue.comments,
-            };
-            preview.generate_png().unwrap()
-        });
-
-        // And write it to disk
-        tokio::fs::write(format!("output/preview/{post_dash_case}.png"), preview_png).await?;
+        Command::Export { output } => {
+            let (source, _site) = open_source(&config).await?;
+            let count = blog::export(&source, &output).await?;
+            info!("Exported {count} article(s) into {}", output.display());
+            Ok(())
+        }
     }
-
-    let mut profil_picture_url = user.avatar_url;
-    profil_picture_url.set_query(Some("v=4&s=100"));
-
-    create_and_write_template_into(
-        "output/index.html",
-        IndexTemplate {
-            profil_picture_url,
-            username: user.name.clone(),
-            description: "A chill and fun blog about Rust stuff and the journey of building my company: Meilisearch".to_string(),
-            html_bio: html_bio_owner,
-            url: homepage_url.clone(),
-            preview_url: format!("{homepage}preview/homepage.png"),
-            articles,
-        },
-    )
-    .await?;
-
-    let channel = Channel {
-        title: format!("{}'s blog", user.name),
-        items,
-        link: homepage_url.to_string(),
-        ..Default::default()
-    };
-    fs::write("output/atom.xml", channel.to_string())
-        .await
-        .context("writing into `output/feed.atom`")?;
-
-    Ok(())
 }
 
-#[derive(Deserialize)]
-struct User {
-    avatar_url: Url,
-    name: String,
-    bio: String,
-}
-
-#[derive(Template)]
-#[template(path = "index.html", escape = "none")]
-struct IndexTemplate {
-    profil_picture_url: Url,
-    username: String,
-    description: String,
-    url: Url,
-    preview_url: String,
-    html_bio: String,
-    articles: Vec<ArticleInList>,
-}
-
-struct ArticleInList {
-    title: String,
-    synopsis: String,
-    url: String,
-    publish_date: String,
-    guest_user: Option<String>,
-    comments_count: u32,
-}
-
-#[derive(Template)]
-#[template(path = "article.html", escape = "none")]
-struct ArticleTemplate {
-    profil_picture_url: Url,
-    username: String,
-    owner: String,
-    repository: String,
-    issue_number: u64,
-    html_bio: String,
-    url: String,
-    publish_date: String,
-    title: String,
-    description: String,
-    html_content: String,
-    preview_url: String,
-    comments_count: u32,
-    reaction_counts: ReactionCounts,
-}
-
-#[derive(Template)]
-#[template(path = "redirect.html", escape = "none")]
-struct RedirectTemplate {
-    redirect_url: String,
-}
-
-fn linkify_at_references(bio: impl AsRef<str>) -> String {
-    regex::Regex::new(r"(@(\w+))")
-        .unwrap()
-        .replace_all(bio.as_ref(), format!("<a href=\"{GITHUB_BASE_URL}$2\">$1</a>"))
-        .into_owned()
-}
-
-fn insert_table_class_to_table(html: impl AsRef<str>) -> String {
-    regex::Regex::new(r#"(<table) (role="table">)"#)
-        .unwrap()
-        .replace_all(html.as_ref(), r#"$1 class="table table-striped" $2"#)
-        .into_owned()
-}
-
-fn insert_anchor_to_headers(html: impl AsRef<str>) -> String {
-    regex::Regex::new(r#"<(h[234]) (.*)>(.*)</(h[234])>"#)
-        .unwrap()
-        .replace_all(html.as_ref(), |captures: &Captures| {
-            assert_eq!(&captures[1], &captures[4]);
-            let header = &captures[1];
-            let header_attrs = &captures[2];
-            let text = &captures[3];
-            let dash_case = correct_dash_case(&captures[3]);
-            format!(r##"<{header} id="{dash_case}" {header_attrs}><a href="#{dash_case}">{text}</a></{header}>"##)
-        })
-        .into_owned()
-}
-
-fn replace_img_srcs_with_hashes(html: impl AsRef<str>) -> (HashMap<String, PathBuf>, String) {
-    use kuchiki::parse_html;
-    use kuchiki::traits::*;
-
-    let mut urls_to_local_path = HashMap::new();
-    let document = parse_html().one(html.as_ref());
-
-    for a_element in document.select("a > img").unwrap() {
-        let a_node = a_element.as_node().parent().unwrap();
-        let a_element_ref = a_node.as_element().unwrap();
-        let img_element_ref = a_element.as_node().as_element().unwrap();
-        let img_src =
-            img_element_ref.attributes.borrow().get("src").map(|s| s.to_string()).unwrap();
+/// Logs on stderr, the `RUST_LOG` env var overrides the levels.
+///
+/// The spans are logged when they close, with their duration, to see
+/// which phase of which article is slow. They are logged when verbose or
+/// in JSON, the text logs would be too noisy otherwise.
+fn init_logging(verbose: bool, format: LogFormat) {
+    let level = if verbose { "blog=debug" } else { "blog=info" };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+    let span_events = match format {
+        LogFormat::Text if !verbose => FmtSpan::NONE,
+        _ => FmtSpan::CLOSE,
+    };
 
-        let local_path = hash_path_from_url(&img_src);
-        urls_to_local_path.insert(img_src.to_string(), local_path.clone());
-        a_element_ref.attributes.borrow_mut().insert("href", local_path.display().to_string());
-        img_element_ref.attributes.borrow_mut().insert("src", local_path.display().to_string());
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(span_events)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_writer(std::io::stderr);
+    match format {
+        LogFormat::Text => subscriber.with_target(false).init(),
+        LogFormat::Json => subscriber.json().with_span_list(true).init(),
     }
-
-    (urls_to_local_path, document.to_string())
 }
 
-fn hash_path_from_url(url: impl AsRef<str>) -> PathBuf {
-    let hasher = BuildHasherDefault::<DefaultHasher>::default();
-    let hash = hasher.hash_one(url.as_ref());
-    let url = Url::parse(url.as_ref()).unwrap();
-    let url_path = url.path();
-    let path = PathBuf::new().join("assets").join("images").join(format!("{hash:x}"));
-    match Path::new(url_path).extension() {
-        Some(extension) => path.with_extension(extension.to_str().unwrap()),
-        None => path.with_extension("png"),
+/// Generates the website to serve it. The errors are only logged, the server
+/// keeps serving the previous website until the next change fixes them.
+async fn serve_build(source: &Source, site: &Site, config: &Config) {
+    let result = match blog::build(source, site, config).await {
+        Ok(report) => report.finish(config),
+        Err(e) => Err(e),
+    };
+    log_rate_limit(source);
+    if let Err(e) = result {
+        error!("{e:#}");
     }
 }
 
-fn synopsis(s: impl AsRef<str>) -> String {
-    let html = scraper::Html::parse_fragment(s.as_ref());
-    fn get_first_html_comment(document: &Html) -> Option<&str> {
-        for node in document.tree.nodes() {
-            if let Some(comment) = node.value().as_comment() {
-                return Some(comment);
-            }
-        }
-        None
+fn log_rate_limit(source: &Source) {
+    if let Some(rate_limit) = source.rate_limit() {
+        info!(remaining = rate_limit.remaining, limit = rate_limit.limit, "{rate_limit}");
     }
-
-    get_first_html_comment(&html).map_or_else(String::new, ToOwned::to_owned)
 }
 
-fn correct_dash_case(s: impl AsRef<str>) -> String {
-    use slice_group_by::StrGroupBy;
-
-    let mut output = String::new();
-    for group in s.as_ref().linear_group_by_key(|x| x.is_ascii_alphanumeric()) {
-        if let Some(x) = group.chars().next() {
-            if x.is_alphanumeric() {
-                output.extend(group.chars().map(|x| x.to_ascii_lowercase()));
-            } else {
-                output.push('-');
-            }
-        }
+/// The templates are compiled into the binary, we must recompile it to take
+/// a change into account and replace the current process with the new binary.
+fn recompile_and_restart() -> anyhow::Result<()> {
+    info!("The templates changed, recompiling...");
+    // Once replaced, the path of the running binary is no longer valid on Linux
+    let executable = std::env::current_exe()?;
+    let mut cargo = std::process::Command::new("cargo");
+    cargo.arg("build");
+    if !cfg!(debug_assertions) {
+        cargo.arg("--release");
     }
-
-    if output.ends_with('-') {
-        output.pop();
+    let status = cargo.status().context("while running `cargo build`")?;
+    if !status.success() {
+        // Keep serving the previous version until the templates are fixed
+        error!("failed to recompile, fix the templates and save again");
+        return Ok(());
     }
 
-    output
+    let mut command = std::process::Command::new(executable);
+    command.args(std::env::args_os().skip(1));
+    restart(command)
 }
 
-#[derive(Debug, Default)]
-struct ReactionCounts {
-    heart: usize,
-    plus_one: usize,
-    laugh: usize,
-    confused: usize,
-    hooray: usize,
-    minus_one: usize,
-    rocket: usize,
-    eyes: usize,
+#[cfg(unix)]
+fn restart(mut command: std::process::Command) -> anyhow::Result<()> {
+    use std::os::unix::process::CommandExt;
+    Err(command.exec()).context("while restarting the server")
 }
 
-async fn collect_reactions(
-    handler: &IssueHandler<'_>,
-    issue_id: u64,
-) -> anyhow::Result<ReactionCounts> {
-    let mut output = ReactionCounts::default();
-
-    for reaction in handler.list_reactions(issue_id).per_page(100).send().await? {
-        match reaction.content {
-            ReactionContent::Heart => output.heart += 1,
-            ReactionContent::PlusOne => output.plus_one += 1,
-            ReactionContent::Laugh => output.laugh += 1,
-            ReactionContent::Confused => output.confused += 1,
-            ReactionContent::Hooray => output.hooray += 1,
-            ReactionContent::MinusOne => output.minus_one += 1,
-            ReactionContent::Rocket => output.rocket += 1,
-            ReactionContent::Eyes => output.eyes += 1,
-        }
-    }
-
-    Ok(output)
+#[cfg(not(unix))]
+fn restart(_command: std::process::Command) -> anyhow::Result<()> {
+    bail!("the templates have been recompiled, please restart the server")
 }
 
-async fn create_and_write_template_into(
-    path: impl AsRef<Path>,
-    template: impl Template,
-) -> anyhow::Result<()> {
-    let path = path.as_ref();
-    let mut article_file = File::create(path)
-        .await
-        .with_context(|| format!("When opening {:?}", path.display()))?
-        .into_std()
-        .await;
-    template.write_into(&mut article_file)?;
-    Ok(())
+/// Chooses the source of the articles and gathers everything needed to build the blog.
+async fn open_site(config: &Config) -> anyhow::Result<(Source, Site)> {
+    let email_address = config
+        .email_address
+        .clone()
+        .context("please define the `EMAIL_ADDRESS` env var or the `email_address` config")?;
+    let (source, site) = open_source(config).await?;
+    Ok((source, Site { email_address, ..site }))
 }
 
-fn ignore_not_found(e: io::Error) -> io::Result<()> {
-    if e.kind() == ErrorKind::NotFound {
-        Ok(())
+/// Chooses the source of the articles and gathers the information about the blog.
+async fn open_source(config: &Config) -> anyhow::Result<(Source, Site)> {
+    let owner_repo = config
+        .repository
+        .clone()
+        .context("please define the `GITHUB_REPOSITORY` env var or the `repository` config")?;
+    let email_address = config.email_address.clone().unwrap_or_default();
+    let (owner, repository) = owner_repo
+        .split_once('/')
+        .with_context(|| format!("the repository must be `owner/repo`, not {owner_repo:?}"))?;
+    let (owner, repository) = (owner.to_string(), repository.to_string());
+
+    // Build from a local directory of Markdown files if asked to
+    let (source, homepage) = if let Some(directory) = &config.posts_directory {
+        let homepage = config.homepage.clone().context(
+            "please define the `HOMEPAGE_URL` env var or the `homepage` config \
+             when building from a local directory",
+        )?;
+        (Source::Local(LocalSource::new(directory, &owner)?), homepage)
+    } else if config.api == Api::GraphQl {
+        let source = GraphQlSource::new(&owner, &repository, config)?;
+        let homepage = match config.homepage.clone() {
+            Some(homepage) => homepage,
+            None => source.homepage().await?,
+        };
+        (Source::GraphQl(source), homepage)
     } else {
-        Err(e)
-    }
-}
+        let source = GitHubSource::new(&owner, &repository, config)?;
+        let homepage = match config.homepage.clone() {
+            Some(homepage) => homepage,
+            None => source.homepage().await?,
+        };
+        (Source::GitHub(source), homepage)
+    };
 
-/// Because the Rename struct only has private field we are
-/// forced to serialize/deserialize-trick to extract the from field, for now.
-fn extract_from_field_from_rename(rename: Rename) -> Option<String> {
-    match serde_json::to_value(rename).unwrap()["from"] {
-        serde_json::Value::String(ref s) => Some(s.clone()),
-        _ => None,
-    }
+    Ok((source, Site { owner, repository, email_address, homepage }))
 }
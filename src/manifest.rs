@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::stable_hash;
+use crate::source::{Author, ReactionCounts};
+
+/// The name of the manifest, written at the root of the output.
+const MANIFEST_FILE_NAME: &str = ".build-manifest.json";
+
+/// The templates compiled into the binary, an article
+/// must be rendered again when one of them changes.
+const TEMPLATES: &[&str] = &[
+    include_str!("../templates/base.html"),
+    include_str!("../templates/article.html"),
+    include_str!("../templates/redirect.html"),
+    include_str!("../templates/blog-post-preview.svg"),
+];
+
+/// The code of the generator, an article must be rendered again when the code
+/// shaping its page changes, even if the version didn't. Lists every module.
+const SOURCES: &[&str] = &[
+    include_str!("../Cargo.toml"),
+    include_str!("cache.rs"),
+    include_str!("config.rs"),
+    include_str!("fixtures.rs"),
+    include_str!("github.rs"),
+    include_str!("graphql.rs"),
+    include_str!("html.rs"),
+    include_str!("lib.rs"),
+    include_str!("local.rs"),
+    include_str!("main.rs"),
+    include_str!("manifest.rs"),
+    include_str!("markdown.rs"),
+    include_str!("metadata.rs"),
+    include_str!("output.rs"),
+    include_str!("pipeline.rs"),
+    include_str!("report.rs"),
+    include_str!("retry.rs"),
+    include_str!("serve.rs"),
+    include_str!("source.rs"),
+    include_str!("templates.rs"),
+    include_str!("toc.rs"),
+    include_str!("transform.rs"),
+    include_str!("watch.rs"),
+];
+
+/// What the previous build rendered, used to reuse the unchanged articles.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// The rendered articles by slug.
+    pub articles: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub key: ArticleKey,
+    pub author_name: String,
+    pub publish_date: DateTime<Utc>,
+    /// The slugs of the pages redirecting to the article.
+    pub redirects: Vec<String>,
+}
+
+/// Everything the output of an article depends on, the
+/// article is reused when it didn't change since the last build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArticleKey {
+    pub updated_at: DateTime<Utc>,
+    pub comments_count: Option<u32>,
+    pub reactions: Option<ReactionCounts>,
+    pub template_hash: String,
+    /// The profile of the author displayed on the page, which isn't part of the article.
+    pub author_hash: String,
+}
+
+impl Manifest {
+    /// Reads the manifest of a previous output, an unreadable or missing
+    /// manifest is considered empty and all the articles are rendered again.
+    pub fn read(directory: &Path) -> Manifest {
+        std::fs::read_to_string(directory.join(MANIFEST_FILE_NAME))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn write(&self, directory: &Path) -> anyhow::Result<()> {
+        let path = directory.join(MANIFEST_FILE_NAME);
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(&path, json).with_context(|| format!("while writing {}", path.display()))
+    }
+}
+
+/// Hashes the templates, the code of the generator and the settings they are rendered with.
+pub fn template_hash(settings: impl Debug) -> String {
+    let settings = format!("{TEMPLATES:?}{SOURCES:?}{settings:?}");
+    format!("{:016x}", stable_hash(&settings))
+}
+
+/// Hashes the profile of the author displayed on the articles.
+pub fn author_hash(author: &Author) -> String {
+    let Author { login, name, bio, avatar_url } = author;
+    let profile = format!("{:?}", (login, name, bio, avatar_url.as_str()));
+    format!("{:016x}", stable_hash(&profile))
+}
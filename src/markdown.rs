@@ -0,0 +1,157 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+
+use comrak::adapters::{HeadingAdapter, HeadingMeta};
+use comrak::nodes::{Ast, AstNode, NodeHtmlBlock, NodeLink, NodeValue, Sourcepos};
+use comrak::{format_html_with_plugins, parse_document, Arena, Options, Plugins};
+use url::Url;
+
+use crate::html::escape;
+
+/// Renders GitHub-Flavored Markdown into HTML that looks like the `body_html`
+/// returned by the GitHub API when asking for the `full` media type.
+///
+/// The output goes through the same rewrites than the GitHub one: headings and
+/// paragraphs have a `dir="auto"` attribute, tables a `role="table"` one, images
+/// are wrapped into links and code blocks use the `highlight-source-*` classes.
+/// The code itself is not highlighted, GitHub is the only one to emit the `pl-*` spans.
+///
+/// The relative sources of the images are resolved against the `base` URL, if any.
+/// The raw HTML is kept as is, the Markdown must come from a trusted author.
+pub fn to_html(markdown: &str, base: Option<&Url>) -> String {
+    render(markdown, base, true)
+}
+
+/// Renders Markdown written by anyone, like [`to_html`] but the raw HTML is
+/// omitted and the links and images with a dangerous scheme, like `file:`, emptied.
+pub fn to_safe_html(markdown: &str) -> String {
+    render(markdown, None, false)
+}
+
+fn render(markdown: &str, base: Option<&Url>, raw_html: bool) -> String {
+    let mut options = Options::default();
+    options.extension.strikethrough = true;
+    options.extension.table = true;
+    options.extension.autolink = true;
+    options.extension.tasklist = true;
+    options.extension.footnotes = true;
+    options.extension.alerts = true;
+    options.render.tasklist_classes = true;
+    options.render.unsafe_ = raw_html;
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, markdown, &options);
+
+    // The code blocks are rendered by us and inserted once comrak is done, as it would
+    // omit them like any raw HTML otherwise. The NUL chars can't come from the Markdown.
+    let mut code_blocks = Vec::new();
+
+    // We collect the nodes first as we are going to modify the tree
+    let nodes: Vec<_> = root.descendants().collect();
+    for node in nodes {
+        let mut ast = node.data.borrow_mut();
+        match &mut ast.value {
+            // GitHub removes the comments, like the synopsis one
+            NodeValue::HtmlBlock(NodeHtmlBlock { literal, .. })
+            | NodeValue::HtmlInline(literal)
+                if is_html_comment(literal) =>
+            {
+                drop(ast);
+                node.detach();
+            }
+            NodeValue::CodeBlock(code_block) => {
+                code_blocks.push(github_code_block(&code_block.info, &code_block.literal));
+                code_block.info.clear();
+                code_block.literal = format!("\0{}\0", code_blocks.len() - 1);
+            }
+            NodeValue::Image(NodeLink { url, .. }) => {
+                if let Some(resolved) = base.and_then(|base| base.join(url).ok()) {
+                    *url = resolved.to_string();
+                }
+                let parent_is_link = node
+                    .parent()
+                    .map_or(false, |p| matches!(p.data.borrow().value, NodeValue::Link(_)));
+                if !parent_is_link {
+                    let link = NodeValue::Link(NodeLink { url: url.clone(), title: String::new() });
+                    let start = ast.sourcepos.start;
+                    drop(ast);
+                    let link = arena.alloc(AstNode::new(RefCell::new(Ast::new(link, start))));
+                    node.insert_before(link);
+                    link.append(node);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let mut plugins = Plugins::default();
+    plugins.render.heading_adapter = Some(&GitHubHeadings);
+
+    let mut html = Vec::new();
+    format_html_with_plugins(root, &options, &mut html, &plugins).unwrap();
+    let html = String::from_utf8(html).unwrap();
+
+    let mut html =
+        html.replace("<p>", r#"<p dir="auto">"#).replace("<table>", r#"<table role="table">"#);
+    for (i, code_block) in code_blocks.iter().enumerate() {
+        html = html.replacen(&format!("<pre><code>\0{i}\0</code></pre>"), code_block, 1);
+    }
+    html
+}
+
+struct GitHubHeadings;
+
+impl HeadingAdapter for GitHubHeadings {
+    fn enter(
+        &self,
+        output: &mut dyn Write,
+        heading: &HeadingMeta,
+        _sourcepos: Option<Sourcepos>,
+    ) -> io::Result<()> {
+        write!(output, r#"<h{} dir="auto">"#, heading.level)
+    }
+
+    fn exit(&self, output: &mut dyn Write, heading: &HeadingMeta) -> io::Result<()> {
+        writeln!(output, "</h{}>", heading.level)
+    }
+}
+
+fn is_html_comment(html: &str) -> bool {
+    let html = html.trim();
+    html.starts_with("<!--") && html.ends_with("-->")
+}
+
+/// Wraps the code like GitHub does, the language is used to
+/// find the TextMate scope that the stylesheet relies on.
+fn github_code_block(info: &str, code: &str) -> String {
+    let code = escape(code);
+    match info.split_whitespace().next() {
+        Some(language) => {
+            let scope = textmate_scope(language);
+            let code = code.strip_suffix('\n').unwrap_or(&code);
+            format!(
+                r#"<div class="highlight highlight-{scope} notranslate position-relative overflow-auto" dir="auto"><pre>{code}</pre></div>"#
+            )
+        }
+        None => {
+            format!(r#"<pre class="notranslate"><code class="notranslate">{code}</code></pre>"#)
+        }
+    }
+}
+
+fn textmate_scope(language: &str) -> String {
+    let language = language.to_lowercase();
+    let scope = match language.as_str() {
+        "rs" => "source-rust",
+        "javascript" => "source-js",
+        "typescript" => "source-ts",
+        "sh" | "bash" | "zsh" | "console" => "source-shell",
+        "yml" => "source-yaml",
+        "py" => "source-python",
+        "cpp" | "c++" => "source-c++",
+        "html" => "text-html-basic",
+        "md" | "markdown" => "text-md",
+        _ => return format!("source-{language}"),
+    };
+    scope.to_string()
+}
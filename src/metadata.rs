@@ -0,0 +1,108 @@
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer};
+use url::Url;
+
+use crate::local::parse_date;
+use crate::source::validate_slug;
+
+/// The metadata an author can write in an HTML comment of the issue body, to control
+/// the article without changing its title. The fields are either written on one line
+/// or as YAML on the following lines, the tags are separated by commas.
+///
+/// ```markdown
+/// <!-- meta: slug=my-article, date=2024-03-10, tags=rust, search, cover=https://... -->
+///
+/// <!-- meta:
+/// canonical: https://www.meilisearch.com/blog/my-article
+/// tags: [rust, search]
+/// -->
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Metadata {
+    /// Overrides the slug derived from the title.
+    pub slug: Option<String>,
+    /// Overrides the publish date, the date at which the article has been labeled.
+    #[serde(default, deserialize_with = "deserialize_date")]
+    pub date: Option<DateTime<Utc>>,
+    /// The categories of the article in the RSS feed.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The image used as the social card instead of the generated preview.
+    pub cover: Option<Url>,
+    /// The URL of the original article when it was first published elsewhere.
+    pub canonical: Option<Url>,
+}
+
+impl Metadata {
+    /// Parses the first metadata comment of the Markdown body, if any.
+    pub fn from_body(body: &str) -> anyhow::Result<Metadata> {
+        let html = scraper::Html::parse_fragment(body);
+        let content = html
+            .tree
+            .nodes()
+            .filter_map(|node| node.value().as_comment())
+            .find_map(|comment| metadata_content(comment));
+
+        let metadata: Metadata = match content {
+            None => return Ok(Metadata::default()),
+            Some(content) => match content.split_once('\n') {
+                Some((first_line, yaml)) if first_line.trim().is_empty() => {
+                    serde_yaml::from_str(yaml).context("while parsing the metadata comment")?
+                }
+                _ => parse_inline(content).context("while parsing the metadata comment")?,
+            },
+        };
+
+        if let Some(slug) = &metadata.slug {
+            validate_slug(slug)?;
+        }
+        Ok(metadata)
+    }
+}
+
+/// Whether the HTML comment contains metadata rather than a synopsis.
+pub fn is_metadata_comment(comment: &str) -> bool {
+    metadata_content(comment).is_some()
+}
+
+/// The content of the comment following the `meta:` prefix.
+fn metadata_content(comment: &str) -> Option<&str> {
+    comment.trim_start().strip_prefix("meta:")
+}
+
+/// Parses the comma-separated `key=value` pairs, a value without
+/// a key is another tag when following the `tags` key.
+fn parse_inline(content: &str) -> anyhow::Result<Metadata> {
+    let mut fields = serde_json::Map::new();
+    let mut in_tags = false;
+    for pair in content.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+        match pair.split_once('=') {
+            Some((key, value)) if key.trim() == "tags" => {
+                in_tags = true;
+                fields.insert("tags".to_string(), serde_json::json!([value.trim()]));
+            }
+            Some((key, value)) => {
+                in_tags = false;
+                fields.insert(key.trim().to_string(), value.trim().into());
+            }
+            None if in_tags => {
+                if let Some(serde_json::Value::Array(tags)) = fields.get_mut("tags") {
+                    tags.push(pair.into());
+                }
+            }
+            None => bail!("expected a `key=value` pair, found {pair:?}"),
+        }
+    }
+    serde_json::from_value(fields.into()).map_err(Into::into)
+}
+
+fn deserialize_date<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        Some(date) => parse_date(&date).map(Some).map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
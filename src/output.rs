@@ -0,0 +1,124 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+
+use crate::manifest::Manifest;
+
+/// Written into every directory the build generates. The build refuses to
+/// delete or replace a directory without it, to protect the directories
+/// given as the output by mistake.
+const MARKER_FILE_NAME: &str = ".blog-output";
+
+const MARKER_CONTENT: &str = "This directory has been generated by the blog \
+                              builder, it is replaced by every build.\n";
+
+/// The directory the website is built into before replacing the output, next to it.
+fn staging_path(output: &Path) -> PathBuf {
+    sibling_path(output, ".staging")
+}
+
+/// Where the replaced output is moved while swapping the directories, next to it.
+fn previous_path(output: &Path) -> PathBuf {
+    sibling_path(output, ".previous")
+}
+
+/// Suffixes the name of the output, which ignores the trailing separators
+/// of `site/` that would put the sibling inside the output otherwise.
+fn sibling_path(output: &Path, suffix: &str) -> PathBuf {
+    let mut name = output.file_name().map_or_else(|| OsString::from("output"), OsString::from);
+    name.push(suffix);
+    output.with_file_name(name)
+}
+
+/// Creates an empty staging directory for the output, removing the one
+/// left by an interrupted build. Fails if the output, or anything in the
+/// way, hasn't been generated by a previous build.
+pub fn create_staging(output: &Path) -> anyhow::Result<PathBuf> {
+    ensure_generated(output)?;
+    let staging = staging_path(output);
+    remove_generated(&staging)?;
+    remove_generated(&previous_path(output))?;
+
+    std::fs::create_dir_all(&staging)
+        .with_context(|| format!("while creating {}", staging.display()))?;
+    let marker = staging.join(MARKER_FILE_NAME);
+    std::fs::write(&marker, MARKER_CONTENT)
+        .with_context(|| format!("while writing {}", marker.display()))?;
+    Ok(staging)
+}
+
+/// Checks that the staging directory contains everything
+/// the website needs before it replaces the output.
+pub fn verify(staging: &Path, manifest: &Manifest) -> anyhow::Result<()> {
+    let mut missing = Vec::new();
+    let files = ["index.html", "atom.xml", "preview/homepage.png"].map(String::from);
+    let pages = manifest.articles.iter().flat_map(|(slug, entry)| {
+        let redirects = entry.redirects.iter().map(|redirect| format!("{redirect}.html"));
+        [format!("{slug}.html"), format!("preview/{slug}.png")].into_iter().chain(redirects)
+    });
+
+    for file in files.into_iter().chain(pages) {
+        if !staging.join(&file).is_file() {
+            missing.push(file);
+        }
+    }
+
+    if !missing.is_empty() {
+        bail!(
+            "the build in {} is incomplete, the output has been left untouched, missing: {}",
+            staging.display(),
+            missing.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Replaces the output by the staging directory. The output is only
+/// missing between two renames, it is never left partially written.
+pub fn swap_into_place(staging: &Path, output: &Path) -> anyhow::Result<()> {
+    let previous = previous_path(output);
+    match std::fs::rename(output, &previous) {
+        Ok(()) => (),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+        Err(e) => return Err(e).with_context(|| format!("while moving {} away", output.display())),
+    }
+
+    if let Err(error) = std::fs::rename(staging, output) {
+        // We put the previous output back, there is nothing better to serve
+        let _ = std::fs::rename(&previous, output);
+        return Err(error).with_context(|| {
+            format!("while moving {} to {}", staging.display(), output.display())
+        });
+    }
+
+    remove_generated(&previous)
+}
+
+/// Fails if the directory exists, isn't empty and hasn't been generated by a build.
+fn ensure_generated(directory: &Path) -> anyhow::Result<()> {
+    let mut entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("while reading {}", directory.display())),
+    };
+
+    if entries.next().is_some() && !directory.join(MARKER_FILE_NAME).is_file() {
+        bail!(
+            "refusing to replace {} as it doesn't contain the `{MARKER_FILE_NAME}` file \
+             written by the builds, remove it yourself or choose another output directory",
+            directory.display()
+        );
+    }
+    Ok(())
+}
+
+/// Removes a directory generated by a previous build, does nothing if it doesn't exist.
+fn remove_generated(directory: &Path) -> anyhow::Result<()> {
+    ensure_generated(directory)?;
+    match std::fs::remove_dir_all(directory) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("while removing {}", directory.display())),
+    }
+}
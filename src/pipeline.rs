@@ -0,0 +1,702 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use askama::Template;
+use chrono::{DateTime, Utc};
+use futures::{StreamExt, TryStreamExt};
+use rss::extension::atom::{AtomExtension, Link};
+use rss::{Category, Channel, Guid, Item};
+use tokio::fs::{self, File};
+use tokio::io::{self, ErrorKind};
+use tracing::{debug, info_span, warn, Instrument, Span};
+use url::Url;
+
+use crate::cache::HttpCache;
+use crate::config::{Config, ErrorPolicy};
+use crate::html::{correct_dash_case, linkify_at_references, replace_img_srcs_with_hashes};
+use crate::manifest::{author_hash, template_hash, ArticleKey, Manifest, ManifestEntry};
+use crate::metadata::Metadata;
+use crate::output;
+use crate::report::{ArticleError, BuildReport};
+use crate::source::{Article, ArticleSource};
+use crate::templates::{ArticleInList, ArticleTemplate, IndexTemplate, RedirectTemplate};
+use crate::toc::TableOfContents;
+
+/// The information about the blog that doesn't depend on the articles.
+pub struct Site {
+    /// The login of the owner of the blog.
+    pub owner: String,
+    /// The repository where the comments and reactions are left.
+    pub repository: String,
+    /// The email address displayed in the RSS feed.
+    pub email_address: String,
+    pub homepage: Url,
+}
+
+/// Lists the articles of the source and reports the problems that would
+/// break or degrade the website, without writing anything.
+pub async fn check(source: &impl ArticleSource) -> anyhow::Result<Vec<String>> {
+    let mut problems = Vec::new();
+    let mut slugs = HashMap::new();
+
+    for article in source.articles().await? {
+        let slug = article.slug();
+        debug!("{}", article.name());
+        if article.title.trim().is_empty() {
+            problems.push(format!("{} has an empty title", article.name()));
+        }
+        if let Some(reason) = invalid_reason(&article) {
+            problems.push(reason);
+        }
+        if let Err(error) = Metadata::from_body(&article.body) {
+            problems.push(format!("{slug} has an invalid metadata comment: {error:#}"));
+        }
+        // The first paragraph is used instead, it is worth a look but doesn't break anything
+        if article.explicit_synopsis().is_none() {
+            warn!("{slug} has no synopsis, using its first paragraph instead");
+        }
+        if let Some(title) = slugs.insert(slug.clone(), article.title) {
+            problems.push(format!("{slug} is used by more than one article, like {title:?}"));
+        }
+    }
+    for warning in source.take_warnings() {
+        warn!("{warning}");
+    }
+
+    Ok(problems)
+}
+
+/// Renders the whole blog from the given source into the output folder.
+///
+/// The articles are rendered independently, the ones that fail are
+/// listed in the report and handled according to the error policy.
+/// The articles that didn't change since the previous build are reused.
+/// Several articles are rendered at the same time, the order of
+/// the articles in the index and the feed stays the one of the source.
+/// The website is built into a staging directory that replaces the output
+/// once verified, a failed build leaves the previous output untouched. The
+/// failing articles fail the build when the policy says so, the staging
+/// directory is then left next to the output and the report returned.
+pub async fn build(
+    source: &impl ArticleSource,
+    site: &Site,
+    config: &Config,
+) -> anyhow::Result<BuildReport> {
+    let Site { owner, homepage: homepage_url, .. } = site;
+    let homepage = homepage_url.as_str();
+    let mut report = BuildReport::default();
+
+    // The website is built next to the previous output, which is kept
+    // untouched to reuse the unchanged articles and restore the ones that fail
+    let previous = config.output.as_path();
+    let staging = output::create_staging(previous)?;
+    let output = staging.as_path();
+    let previous_manifest =
+        if config.force { Manifest::default() } else { Manifest::read(previous) };
+    // The articles left out by `only` are published as they were in the previous output
+    let carried_manifest =
+        if config.only.is_some() { Manifest::read(previous) } else { Manifest::default() };
+    let mut manifest = Manifest::default();
+
+    fs::create_dir_all(output.join("preview")).await?;
+    fs::create_dir_all(output.join("assets/images")).await?;
+
+    // Copy the assets
+    fs::copy(&config.homepage_preview, output.join("preview/homepage.png")).await?;
+    for asset in &config.assets {
+        copy_recursively(asset, &output.join(asset))
+            .with_context(|| format!("while copying {}", asset.display()))?;
+    }
+
+    let user = source.author(owner).instrument(info_span!("fetch")).await?;
+    let html_bio_owner = linkify_at_references(user.bio);
+
+    let preview_domain = match &config.preview_domain {
+        Some(domain) => domain.clone(),
+        None => homepage_url.host_str().unwrap_or_default().to_string(),
+    };
+    let mut renderer = ArticleRenderer {
+        site,
+        config,
+        output,
+        previous,
+        template_hash: template_hash((
+            homepage,
+            owner,
+            &site.repository,
+            &config.plausible_domain,
+            &preview_domain,
+            &config.toc_label,
+            config.synopsis_length,
+            config.toc_min_headings,
+            // The articles must be rendered again when a transform is registered
+            &config.transforms,
+        )),
+        preview_domain,
+        cache: HttpCache::new(&config.cache),
+        slugs: HashSet::new(),
+        images_directory: match source.images_directory() {
+            Some(directory) => Some(std::fs::canonicalize(directory)?),
+            None => None,
+        },
+    };
+
+    let mut candidates = Vec::new();
+    for article in source.articles().instrument(info_span!("fetch")).await? {
+        // We report the articles we can't render and continue with the others
+        if let Some(reason) = invalid_reason(&article) {
+            report.warn(format!("{reason}, skipping it"));
+            continue;
+        }
+        candidates.push(article);
+    }
+
+    let owners = slug_owners(&candidates);
+    let mut to_render = Vec::new();
+    for (i, article) in candidates.into_iter().enumerate() {
+        let slug = article.slug();
+        let (owner, title) = &owners[&slug];
+        if *owner != i {
+            report.errors.push(ArticleError {
+                number: article.number,
+                error: format!("{slug} is already used by {title:?}"),
+                slug,
+                kept_previous: false,
+            });
+            continue;
+        }
+        if let Err(error) = Metadata::from_body(&article.body) {
+            let slug = article.slug();
+            report.warn(format!("{slug} has an invalid metadata comment, ignoring it: {error:#}"));
+        }
+        if article.explicit_synopsis().is_none() {
+            let slug = article.slug();
+            report.warn(format!("{slug} has no synopsis, using its first paragraph instead"));
+        }
+
+        to_render.push(article);
+    }
+    for warning in source.take_warnings() {
+        report.warn(warning);
+    }
+    // A typo must not publish a website without any article
+    if let Some(only) = &config.only {
+        anyhow::ensure!(
+            to_render.iter().any(|article| article.slug() == *only),
+            "no article has the {only:?} slug, the output has been left untouched"
+        );
+    }
+
+    // The redirections must never replace the page of another article
+    renderer.slugs = to_render.iter().map(Article::slug).collect();
+    let renderer = &renderer;
+
+    // The results are yielded in the order of the articles,
+    // the articles left out by `only` are not rendered
+    let results: Vec<_> = futures::stream::iter(&to_render)
+        .map(|article| {
+            let slug = article.slug();
+            let left_out = config.only.as_ref().map_or(false, |only| *only != slug);
+            let previous_entry = previous_manifest.articles.get(&slug);
+            let span = info_span!("article", number = article.number, slug);
+            async move {
+                if left_out {
+                    return None;
+                }
+                Some(renderer.render(source, article, previous_entry).instrument(span).await)
+            }
+        })
+        .buffered(config.concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut items = Vec::new();
+    let mut articles = Vec::new();
+    for (article, result) in to_render.iter().zip(results) {
+        let url = article.slug();
+        let previous_entry = previous_manifest.articles.get(&url);
+        match result {
+            None => {
+                let entry = carried_manifest.articles.get(&url);
+                let redirects = entry.map_or(Vec::new(), |e| renderer.redirects(&e.redirects));
+                match entry {
+                    Some(entry)
+                        if restore_previous_version(previous, output, &url, &redirects)? =>
+                    {
+                        let (in_list, item) = renderer.entries(
+                            article,
+                            &entry.author_name,
+                            Some(entry.publish_date),
+                        )?;
+                        articles.push(in_list);
+                        items.push(item);
+                        manifest.articles.insert(url, entry.clone());
+                    }
+                    _ => report.warn(format!("{url} isn't in the previous output, leaving it out")),
+                }
+            }
+            Some(Ok(Rendered { in_list, item, entry, reused })) => {
+                articles.push(in_list);
+                items.push(item);
+                manifest.articles.insert(url, entry);
+                report.rendered += 1;
+                report.reused += usize::from(reused);
+            }
+            Some(Err(error)) => {
+                let redirects =
+                    previous_entry.map_or(Vec::new(), |e| renderer.redirects(&e.redirects));
+                let kept_previous = config.on_article_error == ErrorPolicy::KeepPrevious
+                    && restore_previous_version(previous, output, &url, &redirects)?;
+                if kept_previous {
+                    let (in_list, item) = match previous_entry {
+                        Some(entry) => renderer.entries(
+                            article,
+                            &entry.author_name,
+                            Some(entry.publish_date),
+                        )?,
+                        None => renderer.entries(article, &article.author, None)?,
+                    };
+                    articles.push(in_list);
+                    items.push(item);
+                    if let Some(entry) = previous_entry {
+                        manifest.articles.insert(url.clone(), entry.clone());
+                    }
+                }
+                report.errors.push(ArticleError {
+                    number: article.number,
+                    slug: url,
+                    error: format!("{error:#}"),
+                    kept_previous,
+                });
+            }
+        }
+    }
+
+    let mut profil_picture_url = user.avatar_url;
+    profil_picture_url.set_query(Some("v=4&s=100"));
+
+    create_and_write_template_into(
+        output.join("index.html"),
+        IndexTemplate {
+            profil_picture_url,
+            username: user.name.clone(),
+            description: config.description.clone(),
+            html_bio: html_bio_owner,
+            url: homepage_url.clone(),
+            preview_url: format!("{homepage}preview/homepage.png"),
+            articles,
+            plausible_domain: config.plausible_domain.clone(),
+        },
+    )
+    .instrument(info_span!("render"))
+    .await?;
+
+    let channel = Channel {
+        title: format!("{}'s blog", user.name),
+        items,
+        link: homepage_url.to_string(),
+        ..Default::default()
+    };
+    fs::write(output.join("atom.xml"), channel.to_string())
+        .instrument(info_span!("feed"))
+        .await
+        .context("writing into `atom.xml`")?;
+
+    manifest.write(output)?;
+    output::verify(output, &manifest)?;
+    // A website missing the failed articles must not replace the previous one
+    if config.on_article_error == ErrorPolicy::Fail && !report.errors.is_empty() {
+        return Ok(report);
+    }
+    output::swap_into_place(output, previous)?;
+
+    Ok(report)
+}
+
+/// Everything needed to render an article, shared between the articles.
+struct ArticleRenderer<'a> {
+    site: &'a Site,
+    config: &'a Config,
+    /// The staging directory the website is written into.
+    output: &'a Path,
+    /// The output of the previous build, to reuse the unchanged articles and images.
+    previous: &'a Path,
+    template_hash: String,
+    preview_domain: String,
+    cache: HttpCache,
+    /// The slugs of all the rendered articles.
+    slugs: HashSet<String>,
+    /// The canonical directory the `file:` images can be read from, if any.
+    images_directory: Option<PathBuf>,
+}
+
+/// An article written into the output, rendered or reused from the previous build.
+struct Rendered {
+    in_list: ArticleInList,
+    item: Item,
+    entry: ManifestEntry,
+    reused: bool,
+}
+
+impl ArticleRenderer<'_> {
+    /// Writes the article page, its redirections, images and preview, or copies them
+    /// from the previous build when the article didn't change. Returns the entries
+    /// of the article in the index, in the RSS feed and in the manifest.
+    async fn render(
+        &self,
+        source: &impl ArticleSource,
+        article: &Article,
+        previous_entry: Option<&ManifestEntry>,
+    ) -> anyhow::Result<Rendered> {
+        let Site { owner, repository, homepage, .. } = self.site;
+        let output = self.output;
+        let url = article.slug();
+        let synopsis = article.synopsis_or_first_paragraph(self.config.synopsis_length);
+        debug!("Rendering {url}...");
+
+        // The reactions and the profile of the author don't change the update date of the issue
+        let (reaction_counts, author) = async {
+            let reaction_counts = source.reactions(article).await?;
+            let author = source.author(&article.author).await?;
+            anyhow::Ok((reaction_counts, author))
+        }
+        .instrument(info_span!("fetch"))
+        .await?;
+        let key = ArticleKey {
+            updated_at: article.updated_at,
+            comments_count: article.comments_count,
+            reactions: reaction_counts.clone(),
+            template_hash: self.template_hash.clone(),
+            author_hash: author_hash(&author),
+        };
+
+        if let Some(entry) = previous_entry.filter(|entry| entry.key == key) {
+            let redirects = self.redirects(&entry.redirects);
+            if restore_previous_version(self.previous, output, &url, &redirects)? {
+                debug!("{url} didn't change, reusing the previous version");
+                let (in_list, item) =
+                    self.entries(article, &entry.author_name, Some(entry.publish_date))?;
+                return Ok(Rendered { in_list, item, entry: entry.clone(), reused: true });
+            }
+        }
+
+        let (urls_to_path, body_html) =
+            replace_img_srcs_with_hashes(article.body_html.clone().unwrap_or_default())?;
+
+        let images = urls_to_path.len();
+        futures::stream::iter(urls_to_path)
+            .map(|(url, path)| self.download_image(url, path))
+            .buffer_unordered(self.config.concurrency.max(1))
+            .try_collect::<()>()
+            .instrument(info_span!("images", images))
+            .await?;
+
+        // But we must also create the redirection HTML pages to redirect from the previous
+        // names of the article.
+        let history = source.history(article).instrument(info_span!("fetch")).await?;
+
+        // The slug of the metadata replaces the one of the title, which must keep working
+        let previous_slugs: Vec<_> = history
+            .previous_titles
+            .iter()
+            .chain(Some(&article.title))
+            .map(correct_dash_case)
+            .filter(|slug| *slug != url)
+            .collect();
+        let redirects = self.redirects(&previous_slugs);
+        let publish_date = article.publish_date(history.publish_date);
+        let html_bio = linkify_at_references(author.bio);
+
+        let mut profil_picture_url = author.avatar_url;
+        profil_picture_url.set_query(Some("v=4&s=100"));
+
+        let html_content = self.config.transforms.apply(&body_html)?;
+        let table_of_contents = self.table_of_contents(article, &html_content)?;
+
+        let render = async {
+            for redirect in &redirects {
+                create_and_write_template_into(
+                    output.join(format!("{redirect}.html")),
+                    RedirectTemplate { redirect_url: url.clone() },
+                )
+                .await?;
+            }
+
+            // We create the article HTML pages. We must do that after the redirection
+            // pages to be sure to replace the final HTML page by the article.
+            create_and_write_template_into(
+                output.join(format!("{url}.html")),
+                ArticleTemplate {
+                    profil_picture_url,
+                    username: author.name.clone(),
+                    html_bio: html_bio.clone(),
+                    url: format!("{homepage}{url}"),
+                    publish_date: publish_date.format("%B %d, %Y").to_string(),
+                    title: article.title.clone(),
+                    description: synopsis,
+                    html_content,
+                    table_of_contents,
+                    comments_count: article.comments_count,
+                    reaction_counts,
+                    owner: owner.to_string(),
+                    repository: repository.to_string(),
+                    issue_number: article.number,
+                    preview_url: match &article.metadata.cover {
+                        Some(cover) => cover.to_string(),
+                        None => format!("{homepage}preview/{url}.png"),
+                    },
+                    canonical_url: article.metadata.canonical.as_ref().map(Url::to_string),
+                    plausible_domain: self.config.plausible_domain.clone(),
+                },
+            )
+            .await
+        };
+        render.instrument(info_span!("render")).await?;
+
+        // Generate the preview
+        let preview = crate::Preview {
+            username: article.author.clone(),
+            publish_date: publish_date.format("%B %d, %Y").to_string(),
+            title: article.title.clone(),
+            comment_count: article.comments_count,
+            domain: self.preview_domain.clone(),
+        };
+        let span = info_span!("preview");
+        let images = crate::preview_images(self.config);
+        let preview_png = tokio::task::spawn_blocking(move || {
+            span.in_scope(|| preview.generate_png_with(images))
+        })
+        .await??;
+
+        // And write it to disk
+        fs::write(output.join(format!("preview/{url}.png")), preview_png).await?;
+
+        let (in_list, item) = self.entries(article, &author.name, Some(publish_date))?;
+        let entry = ManifestEntry { key, author_name: author.name, publish_date, redirects };
+        Ok(Rendered { in_list, item, entry, reused: false })
+    }
+
+    /// Renders the table of contents if the article asks for it, or has
+    /// the label for it, or has enough headings to need one.
+    fn table_of_contents(
+        &self,
+        article: &Article,
+        html_content: &str,
+    ) -> anyhow::Result<Option<String>> {
+        let toc = TableOfContents::from_html(html_content)?;
+        let enabled = article.toc.unwrap_or_else(|| {
+            article.labels.contains(&self.config.toc_label)
+                || self.config.toc_min_headings.map_or(false, |min| toc.len() >= min)
+        });
+
+        if enabled && !toc.is_empty() {
+            toc.to_html().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Copies the image from the previous build or downloads it, through the HTTP cache.
+    async fn download_image(&self, url: String, path: PathBuf) -> anyhow::Result<()> {
+        let output = self.output.join(&path);
+
+        // The images are named after the hash of their URL, we reuse the downloaded ones
+        // but read the local ones again, they can be edited without changing their URL
+        let remote = !url.starts_with("file:");
+        if !self.config.force
+            && remote
+            && copy_recursively(&self.previous.join(&path), &output).is_ok()
+        {
+            return Ok(());
+        }
+
+        let cache = self.cache.clone();
+        let fixtures = self.config.fixtures.clone();
+        let images_directory = self.images_directory.clone();
+        // The blocking tasks don't inherit the span of the article
+        let span = Span::current();
+        tokio::task::spawn_blocking(move || {
+            let _entered = span.enter();
+            let body_bytes = match fixtures.replay(&url)? {
+                Some((_, body)) => body,
+                // The images of the local articles are read from the disk,
+                // but never from outside of the directory of the posts
+                None if url.starts_with("file:") => {
+                    let path = Url::parse(&url)?
+                        .to_file_path()
+                        .map_err(|()| anyhow::anyhow!("invalid file URL {url}"))?;
+                    let path = std::fs::canonicalize(&path)
+                        .with_context(|| format!("while reading {}", path.display()))?;
+                    anyhow::ensure!(
+                        images_directory.map_or(false, |directory| path.starts_with(directory)),
+                        "refusing to read {url}, it is not in the directory of the posts"
+                    );
+                    std::fs::read(&path)
+                        .with_context(|| format!("while reading {}", path.display()))?
+                }
+                None => cache.download(&url)?,
+            };
+            fixtures.record(&url, None, &body_bytes)?;
+            std::fs::write(output, body_bytes)?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// The redirections that can be written without replacing the page of another article.
+    fn redirects(&self, previous_slugs: &[String]) -> Vec<String> {
+        previous_slugs.iter().filter(|slug| !self.slugs.contains(*slug)).cloned().collect()
+    }
+
+    /// The entries of the article in the index and in the RSS feed.
+    fn entries(
+        &self,
+        article: &Article,
+        author_name: &str,
+        publish_date: Option<DateTime<Utc>>,
+    ) -> anyhow::Result<(ArticleInList, Item)> {
+        let Site { owner, email_address, homepage, .. } = self.site;
+        let url = article.slug();
+        let synopsis = article.synopsis_or_first_paragraph(self.config.synopsis_length);
+        let publish_date = article.publish_date(publish_date);
+
+        let in_list = ArticleInList {
+            title: article.title.clone(),
+            synopsis: synopsis.clone(),
+            url: url.clone(),
+            publish_date: publish_date.format("%B %d, %Y").to_string(),
+            comments_count: article.comments_count,
+            guest_user: Some(article.author.clone()).filter(|u| !u.eq_ignore_ascii_case(owner)),
+        };
+
+        let item = Item {
+            guid: Some(Guid { value: homepage.join(&url)?.to_string(), permalink: true }),
+            title: Some(article.title.clone()),
+            link: Some(homepage.join(&url)?.to_string()),
+            description: Some(synopsis),
+            author: Some(format!("{email_address} ({author_name})")),
+            atom_ext: Some(AtomExtension {
+                links: vec![Link {
+                    rel: "related".into(),
+                    href: homepage.join(&url)?.to_string(),
+                    title: Some(article.title.clone()),
+                    ..Default::default()
+                }],
+            }),
+            pub_date: Some(publish_date.to_rfc2822()),
+            categories: article
+                .metadata
+                .tags
+                .iter()
+                .map(|tag| Category { name: tag.clone(), domain: None })
+                .collect(),
+            ..Default::default()
+        };
+
+        Ok((in_list, item))
+    }
+}
+
+/// Decides which article is written into the page of each slug, with its index and title.
+///
+/// An article that chose its slug never takes the page of an article whose slug
+/// comes from its title, otherwise the first one keeps it.
+fn slug_owners(articles: &[Article]) -> HashMap<String, (usize, String)> {
+    let explicit = |article: &Article| article.metadata.slug.is_some() || article.slug.is_some();
+    let mut owners: HashMap<_, (usize, String)> = HashMap::new();
+    for (i, article) in articles.iter().enumerate() {
+        let owner = owners.entry(article.slug()).or_insert_with(|| (i, article.title.clone()));
+        if explicit(&articles[owner.0]) && !explicit(article) {
+            *owner = (i, article.title.clone());
+        }
+    }
+    owners
+}
+
+/// Returns the reason why an article can't be rendered, if any.
+fn invalid_reason(article: &Article) -> Option<String> {
+    let name = article.name();
+    if article.body.trim().is_empty() {
+        Some(format!("{name} has an empty body"))
+    } else if article.body_html.is_none() {
+        Some(format!("{name} has no HTML content"))
+    } else {
+        None
+    }
+}
+
+async fn create_and_write_template_into(
+    path: impl AsRef<Path>,
+    template: impl Template,
+) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let mut article_file = File::create(path)
+        .await
+        .with_context(|| format!("When opening {:?}", path.display()))?
+        .into_std()
+        .await;
+    template.write_into(&mut article_file)?;
+    Ok(())
+}
+
+/// Copies a file or a directory and its content.
+fn copy_recursively(src: &Path, dst: &Path) -> io::Result<()> {
+    if src.is_dir() {
+        std::fs::create_dir_all(dst)?;
+        for entry in std::fs::read_dir(src)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+    } else {
+        if let Some(parent) = dst.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(src, dst)?;
+    }
+    Ok(())
+}
+
+/// Copies the page, redirections, preview and images of an article from the previous output.
+///
+/// Returns `false` if the article didn't exist in the previous output.
+fn restore_previous_version(
+    previous: &Path,
+    output: &Path,
+    slug: &str,
+    redirects: &[String],
+) -> io::Result<bool> {
+    let page = format!("{slug}.html");
+    let html = match std::fs::read_to_string(previous.join(&page)) {
+        Ok(html) => html,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+
+    // The redirections must be restored before the page they could replace
+    for redirect in redirects {
+        let redirect = format!("{redirect}.html");
+        copy_recursively(&previous.join(&redirect), &output.join(&redirect))
+            .or_else(ignore_not_found)?;
+    }
+
+    std::fs::write(output.join(&page), &html)?;
+    let preview = format!("preview/{slug}.png");
+    copy_recursively(&previous.join(&preview), &output.join(&preview)).or_else(ignore_not_found)?;
+
+    let images = regex::Regex::new(r#"assets/images/[^"'\s]+"#).unwrap();
+    for image in images.find_iter(&html) {
+        let image = image.as_str();
+        copy_recursively(&previous.join(image), &output.join(image)).or_else(ignore_not_found)?;
+    }
+
+    Ok(true)
+}
+
+fn ignore_not_found(e: io::Error) -> io::Result<()> {
+    if e.kind() == ErrorKind::NotFound {
+        Ok(())
+    } else {
+        Err(e)
+    }
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+use crate::config::{Config, ErrorPolicy};
+
+/// What happened during a build, written as JSON and summarized on stderr.
+#[derive(Debug, Default, Serialize)]
+pub struct BuildReport {
+    /// The number of articles rendered successfully.
+    pub rendered: usize,
+    /// The number of rendered articles reused from the previous build.
+    pub reused: usize,
+    pub warnings: Vec<String>,
+    pub errors: Vec<ArticleError>,
+}
+
+/// An article that failed to render.
+#[derive(Debug, Serialize)]
+pub struct ArticleError {
+    /// The issue of the article, if it has one.
+    pub number: Option<u64>,
+    pub slug: String,
+    pub error: String,
+    /// Whether the previous version of the article has been published instead.
+    pub kept_previous: bool,
+}
+
+impl BuildReport {
+    /// Logs a warning and keeps it for the report.
+    pub fn warn(&mut self, warning: impl Into<String>) {
+        let warning = warning.into();
+        warn!("{warning}");
+        self.warnings.push(warning);
+    }
+
+    /// Writes the report, logs a summary and fails if the policy says so,
+    /// in which case the build didn't replace the output.
+    pub fn finish(&self, config: &Config) -> anyhow::Result<()> {
+        self.write_json(&config.report)?;
+
+        let BuildReport { rendered, reused, warnings, errors } = self;
+        info!(
+            rendered,
+            reused,
+            warnings = warnings.len(),
+            errors = errors.len(),
+            "Rendered {rendered} article(s) ({reused} unchanged) with {} warning(s) and {} error(s)",
+            warnings.len(),
+            errors.len()
+        );
+        for ArticleError { number, slug, error, kept_previous } in errors {
+            let kept = if *kept_previous { ", kept the previous version" } else { "" };
+            let name = match number {
+                Some(number) => format!("#{number} ({slug})"),
+                None => slug.clone(),
+            };
+            error!(number, slug, kept_previous, "{name}{kept}: {error}");
+        }
+
+        match config.on_article_error {
+            ErrorPolicy::Fail if !self.errors.is_empty() => {
+                bail!(
+                    "{} article(s) failed to render, the output has been left untouched",
+                    self.errors.len()
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn write_json(&self, path: &Path) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("while writing {}", path.display()))
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+
+/// The number of times a request is sent before giving up.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// We prefer failing than waiting for a rate limit reset longer than that.
+const MAX_WAIT: Duration = Duration::from_secs(15 * 60);
+
+/// Returns how long to wait before sending a failed request again,
+/// `None` when the failure isn't transient or after too many attempts.
+///
+/// The `status` is missing when the request failed before receiving a response,
+/// the `Retry-After` and `x-ratelimit-*` headers of GitHub are honored.
+pub fn retry_delay(
+    attempt: u32,
+    status: Option<u16>,
+    header: impl Fn(&str) -> Option<String>,
+) -> Option<Duration> {
+    if attempt + 1 >= MAX_ATTEMPTS {
+        return None;
+    }
+
+    let backoff = Duration::from_secs(2u64.pow(attempt));
+    let delay = match status {
+        None | Some(500 | 502 | 503 | 504) => retry_after(&header).unwrap_or(backoff),
+        // GitHub uses 403 for both the rate limits and the permission errors
+        Some(403) => retry_after(&header).or_else(|| rate_limit_reset(&header))?,
+        Some(429) => retry_after(&header).or_else(|| rate_limit_reset(&header)).unwrap_or(backoff),
+        Some(_) => return None,
+    };
+
+    (delay <= MAX_WAIT).then_some(delay)
+}
+
+/// The delay requested by the `Retry-After` header, used by the secondary rate limits.
+fn retry_after(header: impl Fn(&str) -> Option<String>) -> Option<Duration> {
+    header("retry-after")?.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// The delay until the primary rate limit resets, when there are no requests left.
+fn rate_limit_reset(header: impl Fn(&str) -> Option<String>) -> Option<Duration> {
+    if header("x-ratelimit-remaining")?.trim() != "0" {
+        return None;
+    }
+    let reset: i64 = header("x-ratelimit-reset")?.trim().parse().ok()?;
+    let seconds = reset.saturating_sub(Utc::now().timestamp()).max(0) as u64;
+    // The clocks are never perfectly in sync
+    Some(Duration::from_secs(seconds + 1))
+}
+
+/// The quota of requests GitHub still allows, read from the response headers.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    pub reset: DateTime<Utc>,
+}
+
+impl RateLimit {
+    pub fn from_headers(header: impl Fn(&str) -> Option<String>) -> Option<RateLimit> {
+        let number = |name| header(name)?.trim().parse().ok();
+        Some(RateLimit {
+            limit: number("x-ratelimit-limit")?,
+            remaining: number("x-ratelimit-remaining")?,
+            reset: Utc.timestamp_opt(number("x-ratelimit-reset")? as i64, 0).single()?,
+        })
+    }
+}
+
+impl fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let RateLimit { limit, remaining, reset } = self;
+        write!(f, "GitHub API quota: {remaining}/{limit} request(s) remaining, resets at {reset}")
+    }
+}
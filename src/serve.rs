@@ -0,0 +1,155 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Context;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// The path of the Server-Sent Events stream the pages listen to, to know when to reload.
+const LIVE_RELOAD_PATH: &str = "/__livereload";
+
+/// The script injected into every served page to reload it when the website is rebuilt.
+///
+/// The page is also reloaded when the connection comes back after
+/// being lost, i.e. when the server restarted after a recompilation.
+const LIVE_RELOAD_SCRIPT: &str = r#"<script>
+  (function() {
+    let disconnected = false;
+    const source = new EventSource("/__livereload");
+    source.onmessage = () => location.reload();
+    source.onerror = () => disconnected = true;
+    source.onopen = () => disconnected && location.reload();
+  })();
+</script>
+"#;
+
+/// Serves the generated website over HTTP, on localhost, in the background.
+///
+/// The URLs are resolved like GitHub Pages does: `/` serves the `index.html`
+/// file and `/some-article` the `some-article.html` one. The open pages are
+/// reloaded every time something is sent on the `reloads` channel.
+pub async fn serve(
+    directory: PathBuf,
+    port: u16,
+    reloads: broadcast::Sender<()>,
+) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .with_context(|| format!("while listening on port {port}"))?;
+    info!("Serving {} on http://localhost:{port}/", directory.display());
+
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("{e}");
+                    continue;
+                }
+            };
+            let directory = directory.clone();
+            let reloads = reloads.subscribe();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, &directory, reloads).await {
+                    warn!("{e}");
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    directory: &Path,
+    mut reloads: broadcast::Receiver<()>,
+) -> anyhow::Result<()> {
+    let mut buffer = vec![0; 8 * 1024];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+
+    // We only care about the path in the `GET /path HTTP/1.1` request line
+    let path = request.lines().next().and_then(|line| line.split(' ').nth(1)).unwrap_or("/");
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+
+    if path == LIVE_RELOAD_PATH {
+        let header =
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n";
+        stream.write_all(header.as_bytes()).await?;
+        // The stream stays open until the browser closes the page
+        while let Ok(()) | Err(broadcast::error::RecvError::Lagged(_)) = reloads.recv().await {
+            stream.write_all(b"data: reload\n\n").await?;
+        }
+        return Ok(());
+    }
+
+    let response = match resolve_path(directory, path) {
+        Some(file) => {
+            let mut body = tokio::fs::read(&file).await?;
+            if file.extension().map_or(false, |ext| ext == "html") {
+                body = inject_live_reload(body);
+            }
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                content_type(&file),
+                body.len(),
+            );
+            [header.into_bytes(), body].concat()
+        }
+        None => {
+            let body = "Not Found";
+            let header = format!(
+                "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len(),
+            );
+            [header, body.to_string()].concat().into_bytes()
+        }
+    };
+
+    stream.write_all(&response).await?;
+    stream.shutdown().await.map_err(Into::into)
+}
+
+/// Adds the live reload script at the end of the body of the page.
+fn inject_live_reload(html: Vec<u8>) -> Vec<u8> {
+    let html = String::from_utf8_lossy(&html);
+    match html.rfind("</body>") {
+        Some(index) => [&html[..index], LIVE_RELOAD_SCRIPT, &html[index..]].concat(),
+        None => [&html, LIVE_RELOAD_SCRIPT].concat(),
+    }
+    .into_bytes()
+}
+
+/// Finds the file to serve and makes sure we never serve files out of the directory.
+fn resolve_path(directory: &Path, path: &str) -> Option<PathBuf> {
+    let relative = PathBuf::from(path.trim_start_matches('/'));
+    if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return None;
+    }
+
+    let file = directory.join(&relative);
+    if file.is_dir() {
+        Some(file.join("index.html")).filter(|f| f.is_file())
+    } else if file.is_file() {
+        Some(file)
+    } else {
+        Some(file.with_extension("html")).filter(|f| f.is_file())
+    }
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("js") => "application/javascript",
+        Some("xml") => "application/xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,228 @@
+use std::future::Future;
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::html::{correct_dash_case, escape, first_paragraph, synopsis};
+use crate::metadata::Metadata;
+use crate::{GitHubSource, GraphQlSource, LocalSource, RateLimit};
+
+/// Anything the blog can be built from: GitHub issues, a directory of files...
+///
+/// The build pipeline only talks to this trait, it never knows where
+/// the articles, authors or reactions are coming from.
+pub trait ArticleSource {
+    /// Lists all the articles that must be published.
+    fn articles(&self) -> impl Future<Output = anyhow::Result<Vec<Article>>> + Send;
+
+    /// Fetches the profile of an author by its login.
+    fn author(&self, login: &str) -> impl Future<Output = anyhow::Result<Author>> + Send;
+
+    /// Fetches the previous titles and the publish date of an article.
+    fn history(
+        &self,
+        article: &Article,
+    ) -> impl Future<Output = anyhow::Result<ArticleHistory>> + Send;
+
+    /// Counts the reactions left on an article, if the source knows them.
+    fn reactions(
+        &self,
+        article: &Article,
+    ) -> impl Future<Output = anyhow::Result<Option<ReactionCounts>>> + Send;
+
+    /// Takes the problems met while listing the articles that
+    /// didn't stop the build, like the skipped pull requests.
+    fn take_warnings(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// The directory the images with a `file:` URL can be read from. Only the local
+    /// sources have one, the other articles can't read anything from the disk.
+    fn images_directory(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// One of the sources the blog can be built from, chosen at runtime.
+pub enum Source {
+    GitHub(GitHubSource),
+    GraphQl(GraphQlSource),
+    Local(LocalSource),
+}
+
+impl Source {
+    /// The quota of GitHub API requests left, if the source uses it.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        match self {
+            Source::GitHub(source) => source.rate_limit(),
+            Source::GraphQl(source) => source.rate_limit(),
+            Source::Local(_) => None,
+        }
+    }
+}
+
+impl ArticleSource for Source {
+    async fn articles(&self) -> anyhow::Result<Vec<Article>> {
+        match self {
+            Source::GitHub(source) => source.articles().await,
+            Source::GraphQl(source) => source.articles().await,
+            Source::Local(source) => source.articles().await,
+        }
+    }
+
+    async fn author(&self, login: &str) -> anyhow::Result<Author> {
+        match self {
+            Source::GitHub(source) => source.author(login).await,
+            Source::GraphQl(source) => source.author(login).await,
+            Source::Local(source) => source.author(login).await,
+        }
+    }
+
+    async fn history(&self, article: &Article) -> anyhow::Result<ArticleHistory> {
+        match self {
+            Source::GitHub(source) => source.history(article).await,
+            Source::GraphQl(source) => source.history(article).await,
+            Source::Local(source) => source.history(article).await,
+        }
+    }
+
+    async fn reactions(&self, article: &Article) -> anyhow::Result<Option<ReactionCounts>> {
+        match self {
+            Source::GitHub(source) => source.reactions(article).await,
+            Source::GraphQl(source) => source.reactions(article).await,
+            Source::Local(source) => source.reactions(article).await,
+        }
+    }
+
+    fn take_warnings(&self) -> Vec<String> {
+        match self {
+            Source::GitHub(source) => source.take_warnings(),
+            Source::GraphQl(source) => source.take_warnings(),
+            Source::Local(source) => source.take_warnings(),
+        }
+    }
+
+    fn images_directory(&self) -> Option<&Path> {
+        match self {
+            Source::GitHub(source) => source.images_directory(),
+            Source::GraphQl(source) => source.images_directory(),
+            Source::Local(source) => source.images_directory(),
+        }
+    }
+}
+
+/// The names of the pages and directories generated next to the articles.
+const RESERVED_SLUGS: [&str; 4] = ["index", "atom", "assets", "preview"];
+
+/// Fails if the slug chosen by an author can't be the name of the page of an article:
+/// it must be made of lowercase letters, digits and dashes and not replace another page.
+pub fn validate_slug(slug: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !slug.is_empty() && correct_dash_case(slug) == slug,
+        "invalid slug {slug:?}, only lowercase letters, digits and single dashes are allowed"
+    );
+    anyhow::ensure!(!RESERVED_SLUGS.contains(&slug), "the {slug:?} slug is reserved");
+    Ok(())
+}
+
+pub struct Article {
+    /// The issue the comments and reactions are left on, the
+    /// local articles without an issue have none.
+    pub number: Option<u64>,
+    pub title: String,
+    /// Overrides the slug derived from the title.
+    pub slug: Option<String>,
+    /// The login of the author of the article.
+    pub author: String,
+    pub labels: Vec<String>,
+    /// Overrides the synopsis extracted from the body.
+    pub synopsis: Option<String>,
+    /// Forces or disables the table of contents, decided by the labels
+    /// and the number of headings when missing.
+    pub toc: Option<bool>,
+    /// The metadata comment of the body, empty when missing or invalid.
+    pub metadata: Metadata,
+    /// The raw Markdown body.
+    pub body: String,
+    /// The body rendered in HTML.
+    pub body_html: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// The last time the article has been modified, used to skip the unchanged ones.
+    pub updated_at: DateTime<Utc>,
+    /// The number of comments, if the source knows it.
+    pub comments_count: Option<u32>,
+}
+
+impl Article {
+    /// The slug used in the URL of the article, derived from the title by default.
+    pub fn slug(&self) -> String {
+        let slug = self.metadata.slug.as_ref().or(self.slug.as_ref());
+        slug.cloned().unwrap_or_else(|| correct_dash_case(&self.title))
+    }
+
+    /// Names the article in the logs, by its issue number and slug or only its slug.
+    pub fn name(&self) -> String {
+        match self.number {
+            Some(number) => format!("#{number} ({})", self.slug()),
+            None => self.slug(),
+        }
+    }
+
+    /// The issue of an article fetched from GitHub, which always has one.
+    pub fn issue(&self) -> anyhow::Result<u64> {
+        self.number.with_context(|| format!("{} has no issue", self.slug()))
+    }
+
+    /// The date of the metadata, or the given one, or the creation date of the article.
+    pub fn publish_date(&self, published_at: Option<DateTime<Utc>>) -> DateTime<Utc> {
+        self.metadata.date.or(published_at).unwrap_or(self.created_at)
+    }
+
+    /// The synopsis written by the author, in the first HTML comment of the body
+    /// or in the front matter.
+    pub fn explicit_synopsis(&self) -> Option<String> {
+        self.synopsis
+            .clone()
+            .or_else(|| Some(synopsis(&self.body)).filter(|s| !s.trim().is_empty()))
+    }
+
+    /// The explicit synopsis or, when missing, the beginning of the first paragraph.
+    pub fn synopsis_or_first_paragraph(&self, max_length: usize) -> String {
+        // The synopsis is inserted as HTML, unlike the text of the paragraph
+        self.explicit_synopsis().unwrap_or_else(|| {
+            escape(&first_paragraph(self.body_html.as_deref().unwrap_or_default(), max_length))
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct Author {
+    pub login: String,
+    pub name: String,
+    pub bio: String,
+    pub avatar_url: Url,
+}
+
+#[derive(Default, Clone)]
+pub struct ArticleHistory {
+    /// The previous titles of the article, used to create redirections.
+    pub previous_titles: Vec<String>,
+    /// The date at which the article has been published,
+    /// falls back to the creation date when missing.
+    pub publish_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReactionCounts {
+    pub heart: usize,
+    pub plus_one: usize,
+    pub laugh: usize,
+    pub confused: usize,
+    pub hooray: usize,
+    pub minus_one: usize,
+    pub rocket: usize,
+    pub eyes: usize,
+}
@@ -0,0 +1,59 @@
+use askama::Template;
+use url::Url;
+
+use crate::source::ReactionCounts;
+
+#[derive(Template)]
+#[template(path = "index.html", escape = "none")]
+pub struct IndexTemplate {
+    pub profil_picture_url: Url,
+    pub username: String,
+    pub description: String,
+    pub url: Url,
+    pub preview_url: String,
+    pub html_bio: String,
+    pub articles: Vec<ArticleInList>,
+    pub plausible_domain: Option<String>,
+}
+
+pub struct ArticleInList {
+    pub title: String,
+    pub synopsis: String,
+    pub url: String,
+    pub publish_date: String,
+    pub guest_user: Option<String>,
+    /// The number of comments, hidden when unknown.
+    pub comments_count: Option<u32>,
+}
+
+#[derive(Template)]
+#[template(path = "article.html", escape = "none")]
+pub struct ArticleTemplate {
+    pub profil_picture_url: Url,
+    pub username: String,
+    pub owner: String,
+    pub repository: String,
+    /// The comments and reactions are hidden without an issue.
+    pub issue_number: Option<u64>,
+    pub html_bio: String,
+    pub url: String,
+    pub publish_date: String,
+    pub title: String,
+    pub description: String,
+    pub html_content: String,
+    /// The nested lists of links to the headings, when enabled for the article.
+    pub table_of_contents: Option<String>,
+    pub preview_url: String,
+    /// The URL of the original article when it was first published elsewhere.
+    pub canonical_url: Option<String>,
+    /// The counts are hidden when unknown, like for the local articles.
+    pub comments_count: Option<u32>,
+    pub reaction_counts: Option<ReactionCounts>,
+    pub plausible_domain: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "redirect.html", escape = "none")]
+pub struct RedirectTemplate {
+    pub redirect_url: String,
+}
@@ -0,0 +1,92 @@
+use html5ever::{local_name, namespace_url, ns, QualName};
+use kuchiki::traits::*;
+use kuchiki::NodeRef;
+
+use crate::transform::{new_element, select};
+
+/// The `h2`, `h3` and `h4` headings of an article, read from its transformed HTML
+/// so that they link to the ids given by the [`HeadingAnchors`] transform.
+///
+/// [`HeadingAnchors`]: crate::HeadingAnchors
+pub struct TableOfContents {
+    headings: Vec<Heading>,
+}
+
+struct Heading {
+    level: u8,
+    id: String,
+    text: String,
+}
+
+impl TableOfContents {
+    /// Lists the headings with an id, in the order of the article.
+    pub fn from_html(html: &str) -> anyhow::Result<TableOfContents> {
+        let context = QualName::new(None, ns!(html), local_name!("body"));
+        let document = kuchiki::parse_fragment(context, Vec::new()).one(html);
+
+        let mut headings = Vec::new();
+        for heading in select(&document, "h2[id], h3[id], h4[id]")? {
+            let level = heading.name.local.as_bytes()[1] - b'0';
+            let id = heading.attributes.borrow().get("id").unwrap_or_default().to_string();
+            let text = heading.as_node().text_contents();
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            headings.push(Heading { level, id, text });
+        }
+
+        Ok(TableOfContents { headings })
+    }
+
+    /// The number of headings in the table of contents.
+    pub fn len(&self) -> usize {
+        self.headings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headings.is_empty()
+    }
+
+    /// Renders the headings as nested lists of links, a heading deeper than the previous
+    /// one starts a list inside it. A heading shallower than the previous one but deeper
+    /// than its parent, like an `h3` after an `h4`, joins the list of the previous one.
+    pub fn to_html(&self) -> anyhow::Result<String> {
+        let root_level = self.headings.iter().map(|h| h.level).min().unwrap_or(2);
+        let root = new_element("ul", []);
+        let mut lists = vec![(root_level, root.clone())];
+
+        for Heading { level, id, text } in &self.headings {
+            while lists.len() > 1 && lists.last().map_or(false, |(l, _)| l > level) {
+                lists.pop();
+            }
+            let (list_level, list) = lists.last().cloned().unwrap();
+            let list = match list.last_child() {
+                Some(item) if list_level < *level => {
+                    let nested = match item.last_child() {
+                        Some(nested) if is_list(&nested) => nested,
+                        _ => {
+                            let nested = new_element("ul", []);
+                            item.append(nested.clone());
+                            nested
+                        }
+                    };
+                    lists.push((*level, nested.clone()));
+                    nested
+                }
+                _ => list,
+            };
+
+            let link = new_element("a", [("href", format!("#{id}"))]);
+            link.append(NodeRef::new_text(text.as_str()));
+            let item = new_element("li", []);
+            item.append(link);
+            list.append(item);
+        }
+
+        let mut output = Vec::new();
+        root.serialize(&mut output)?;
+        String::from_utf8(output).map_err(Into::into)
+    }
+}
+
+fn is_list(node: &NodeRef) -> bool {
+    node.as_element().map_or(false, |element| element.name.local == local_name!("ul"))
+}
@@ -0,0 +1,189 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::Context;
+use html5ever::{local_name, namespace_url, ns, LocalName, QualName};
+use kuchiki::traits::*;
+use kuchiki::{Attribute, ElementData, ExpandedName, NodeDataRef, NodeRef};
+
+use crate::html::github_slug;
+
+/// A rewrite of the HTML of the articles, applied on the parsed DOM
+/// so that it doesn't depend on how the HTML is formatted.
+pub trait HtmlTransform: Send + Sync {
+    /// The name of the transform, used in the errors and logs.
+    fn name(&self) -> &'static str;
+
+    /// Rewrites the fragment in place, `root` is the parent of the top-level nodes.
+    fn transform(&self, root: &NodeRef) -> anyhow::Result<()>;
+}
+
+/// The transforms applied to the body of every article, in order.
+///
+/// The default ones insert the anchors into the headings and the classes into the
+/// tables, more can be registered on the configuration before building the blog.
+#[derive(Clone)]
+pub struct Transforms {
+    transforms: Vec<Arc<dyn HtmlTransform>>,
+}
+
+impl Transforms {
+    /// No transform at all, the HTML is only normalized.
+    pub fn empty() -> Transforms {
+        Transforms { transforms: Vec::new() }
+    }
+
+    /// Appends a transform, applied after the already registered ones.
+    pub fn register(&mut self, transform: impl HtmlTransform + 'static) {
+        self.transforms.push(Arc::new(transform));
+    }
+
+    /// Parses the HTML fragment, applies the transforms and serializes it again.
+    pub fn apply(&self, html: &str) -> anyhow::Result<String> {
+        let context = QualName::new(None, ns!(html), local_name!("body"));
+        let document = kuchiki::parse_fragment(context, Vec::new()).one(html);
+        // The fragment is parsed under an `<html>` element standing for the context
+        let root = document.first_child().context("the parsed HTML fragment has no root")?;
+
+        for transform in &self.transforms {
+            transform
+                .transform(&root)
+                .with_context(|| format!("while applying the {} transform", transform.name()))?;
+        }
+
+        let mut output = Vec::new();
+        for child in root.children() {
+            child.serialize(&mut output)?;
+        }
+        String::from_utf8(output).map_err(Into::into)
+    }
+}
+
+impl Default for Transforms {
+    fn default() -> Transforms {
+        let mut transforms = Transforms::empty();
+        transforms.register(HeadingAnchors);
+        transforms.register(TableClasses);
+        transforms
+    }
+}
+
+impl fmt::Debug for Transforms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.transforms.iter().map(|t| t.name())).finish()
+    }
+}
+
+/// Gives an id to the headings and wraps the content of the `h2`, `h3` and `h4` ones
+/// into a link to themselves.
+///
+/// The ids are the ones GitHub gives, computed from the text of the headings and
+/// suffixed by `-1`, `-2`... when used more than once. The permalinks GitHub inserts
+/// are removed and the `#user-content-` links to the headings point to the new ids.
+pub struct HeadingAnchors;
+
+impl HtmlTransform for HeadingAnchors {
+    fn name(&self) -> &'static str {
+        "heading anchors"
+    }
+
+    fn transform(&self, root: &NodeRef) -> anyhow::Result<()> {
+        for permalink in select(root, r#"a.anchor[id^="user-content-"]"#)? {
+            permalink.as_node().detach();
+        }
+        // GitHub wraps the headings into a `div` to display the permalinks next to them
+        for wrapper in select(root, "div.markdown-heading")? {
+            let node = wrapper.as_node();
+            for child in node.children().collect::<Vec<_>>() {
+                node.insert_before(child);
+            }
+            node.detach();
+        }
+
+        // The ids are numbered like GitHub does, in the order of all the headings
+        let mut occurrences = HashMap::new();
+        let mut ids = HashSet::new();
+        for heading in select(root, "h1, h2, h3, h4, h5, h6")? {
+            let node = heading.as_node();
+            let id = unique_id(&mut occurrences, github_slug(&node.text_contents()));
+            ids.insert(id.clone());
+
+            if matches!(&*heading.name.local, "h2" | "h3" | "h4") {
+                let link = new_element("a", [("href", format!("#{id}"))]);
+                // Appending a child detaches it, the children must be collected first
+                for child in node.children().collect::<Vec<_>>() {
+                    link.append(child);
+                }
+                node.append(link);
+            }
+            heading.attributes.borrow_mut().insert("id", id);
+        }
+
+        for link in select(root, r##"a[href^="#user-content-"]"##)? {
+            let mut attributes = link.attributes.borrow_mut();
+            let href = attributes.get("href").unwrap_or_default();
+            if let Some(id) = href.strip_prefix("#user-content-").filter(|id| ids.contains(*id)) {
+                let href = format!("#{id}");
+                attributes.insert("href", href);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Suffixes the slug with a number when already used, like GitHub does.
+fn unique_id(occurrences: &mut HashMap<String, usize>, slug: String) -> String {
+    let slug = if slug.is_empty() { "section".to_string() } else { slug };
+    let mut id = slug.clone();
+    while occurrences.contains_key(&id) {
+        let count = occurrences.entry(slug.clone()).or_default();
+        *count += 1;
+        id = format!("{slug}-{count}");
+    }
+    occurrences.insert(id.clone(), 0);
+    id
+}
+
+/// Gives the Bootstrap classes to the tables.
+pub struct TableClasses;
+
+impl HtmlTransform for TableClasses {
+    fn name(&self) -> &'static str {
+        "table classes"
+    }
+
+    fn transform(&self, root: &NodeRef) -> anyhow::Result<()> {
+        for table in select(root, "table")? {
+            let mut attributes = table.attributes.borrow_mut();
+            let class = match attributes.get("class") {
+                Some(class) => format!("{class} table table-striped"),
+                None => "table table-striped".to_string(),
+            };
+            attributes.insert("class", class);
+        }
+        Ok(())
+    }
+}
+
+/// The elements matching the CSS selector, collected to be modified safely.
+pub(crate) fn select(
+    root: &NodeRef,
+    selector: &str,
+) -> anyhow::Result<Vec<NodeDataRef<ElementData>>> {
+    let elements =
+        root.select(selector).map_err(|()| anyhow::anyhow!("invalid selector {selector:?}"))?;
+    Ok(elements.collect())
+}
+
+/// Creates an HTML element with the given attributes.
+pub(crate) fn new_element<'a>(
+    name: &str,
+    attributes: impl IntoIterator<Item = (&'a str, String)>,
+) -> NodeRef {
+    let attributes = attributes
+        .into_iter()
+        .map(|(name, value)| (ExpandedName::new(ns!(), name), Attribute { prefix: None, value }));
+    NodeRef::new_element(QualName::new(None, ns!(html), LocalName::from(name)), attributes)
+}
@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// The delay between two scans of the watched directories.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watches files and directories for changes by regularly scanning them.
+///
+/// Polling is simple and works the same on every platform,
+/// the number of files of a blog is small enough for it.
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    snapshot: BTreeMap<PathBuf, (SystemTime, u64)>,
+}
+
+impl Watcher {
+    pub fn new(paths: Vec<PathBuf>) -> Watcher {
+        let snapshot = snapshot(&paths);
+        Watcher { paths, snapshot }
+    }
+
+    /// Waits for a change and returns the files that have been
+    /// created, modified or removed since the previous call.
+    pub async fn changed(&mut self) -> Vec<PathBuf> {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let paths = self.paths.clone();
+            let snapshot = tokio::task::spawn_blocking(move || snapshot(&paths)).await.unwrap();
+
+            let mut changed: Vec<_> = snapshot
+                .iter()
+                .filter(|(path, metadata)| self.snapshot.get(*path) != Some(metadata))
+                .map(|(path, _)| path.clone())
+                .collect();
+            changed.extend(self.snapshot.keys().filter(|p| !snapshot.contains_key(*p)).cloned());
+
+            self.snapshot = snapshot;
+            if !changed.is_empty() {
+                return changed;
+            }
+        }
+    }
+}
+
+/// The modification time and size of every file under the paths.
+fn snapshot(paths: &[PathBuf]) -> BTreeMap<PathBuf, (SystemTime, u64)> {
+    fn visit(path: &Path, snapshot: &mut BTreeMap<PathBuf, (SystemTime, u64)>) {
+        let Ok(metadata) = path.metadata() else { return };
+        if metadata.is_dir() {
+            for entry in std::fs::read_dir(path).into_iter().flatten().flatten() {
+                visit(&entry.path(), snapshot);
+            }
+        } else if let Ok(modified) = metadata.modified() {
+            snapshot.insert(path.to_path_buf(), (modified, metadata.len()));
+        }
+    }
+
+    let mut snapshot = BTreeMap::new();
+    for path in paths {
+        visit(path, &mut snapshot);
+    }
+    snapshot
+}
@@ -0,0 +1,426 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use blog::{
+    build, check, Api, ArticleSource, Config, ErrorPolicy, Fixtures, GitHubSource, GraphQlSource,
+};
+use common::{default_issues, site, MockGitHub, OWNER, REPOSITORY};
+
+fn read(output: &Path, path: &str) -> String {
+    fs::read_to_string(output.join(path)).unwrap_or_else(|e| panic!("while reading {path}: {e}"))
+}
+
+async fn build_with_rest(config: &Config) -> blog::BuildReport {
+    let source = GitHubSource::new(OWNER, REPOSITORY, config).unwrap();
+    build(&source, &site(), config).await.unwrap()
+}
+
+/// Checks the pages generated from the issues of [`default_issues`].
+fn assert_default_blog(output: &Path) {
+    let index = read(output, "index.html");
+    assert!(index.contains("My First Article"));
+    assert!(index.contains("Second Article"));
+    assert!(index.find("Second Article") < index.find("My First Article"));
+    assert!(!index.contains("Fix the build"), "the pull requests are not articles");
+
+    assert!(index.contains("The synopsis of the second article"));
+    // The first article has no synopsis, the first paragraph is used instead
+    assert_eq!(index.matches("This is the first sentence of my blog post.").count(), 1);
+
+    let first = read(output, "my-first-article.html");
+    assert!(first.contains("This is the first sentence of my blog post."));
+    assert!(first.contains("Clément Renault"));
+
+    let second = read(output, "second-article.html");
+    assert!(second.contains("A Guest"));
+    assert!(second.contains("❤️ 1"));
+    assert!(second.contains("👍 2"));
+    assert!(!second.contains("/images/0.png"), "the images must be downloaded");
+    let image = second.split("assets/images/").nth(1).unwrap().split('"').next().unwrap();
+    let expected = fs::read("assets/keys/0.png").unwrap();
+    assert_eq!(fs::read(output.join("assets/images").join(image)).unwrap(), expected);
+
+    let redirect = read(output, "old-title.html");
+    assert!(redirect.contains("second-article"));
+    assert!(!output.join("fix-the-build.html").exists());
+
+    let feed = read(output, "atom.xml");
+    assert_eq!(feed.matches("<item>").count(), 2);
+    assert!(feed.contains("https://blog.kerollmops.com/second-article"));
+    assert!(output.join("preview/second-article.png").exists());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn build_with_the_rest_api() {
+    let github = MockGitHub::start(default_issues()).await;
+    let config = github.config("rest", Api::Rest);
+
+    let report = build_with_rest(&config).await;
+    assert_eq!(report.rendered, 2);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_default_blog(&config.output);
+    assert_eq!(
+        report.warnings,
+        [
+            "my-first-article has no synopsis, using its first paragraph instead",
+            "#3 is a pull request, skipping it",
+        ]
+    );
+
+    // The issues are listed one per page
+    let requests = github.take_requests();
+    let pages = requests.iter().filter(|r| r.starts_with("GET /repos/Kerollmops/blog/issues?"));
+    assert_eq!(pages.count(), 3, "{requests:#?}");
+
+    // The keys and the profile pictures of the previews come from the mock server too
+    assert!(requests.contains(&"GET /images/R.png 200".to_string()), "{requests:#?}");
+    assert!(requests.contains(&"GET /avatars/Kerollmops.png 200".to_string()), "{requests:#?}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn build_with_the_graphql_api() {
+    let github = MockGitHub::start(default_issues()).await;
+    let config = github.config("graphql", Api::GraphQl);
+
+    let source = GraphQlSource::new(OWNER, REPOSITORY, &config).unwrap();
+    let report = build(&source, &site(), &config).await.unwrap();
+    assert_eq!(report.rendered, 2);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_default_blog(&config.output);
+
+    // Only the images are fetched with the REST API
+    let requests = github.take_requests();
+    assert!(requests.iter().all(|r| r.starts_with("POST /graphql")
+        || r.contains("/images/")
+        || r.contains("/avatars/")));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn report_the_truncated_timelines_with_the_graphql_api() {
+    let mut issues = default_issues();
+    let second = issues.iter_mut().find(|i| i.number == 2).unwrap();
+    second.previous_titles = vec!["Old Title"; 120];
+    let github = MockGitHub::start(issues).await;
+    let config = github.config("graphql-truncated", Api::GraphQl);
+
+    let source = GraphQlSource::new(OWNER, REPOSITORY, &config).unwrap();
+    let report = build(&source, &site(), &config).await.unwrap();
+    let warning = "#2 has too many renames and labels, the oldest ones are ignored";
+    assert!(report.warnings.contains(&warning.to_string()), "{:?}", report.warnings);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fetch_the_owner_with_the_graphql_api() {
+    let github = MockGitHub::start(default_issues()).await;
+    let config = github.config("graphql-owner", Api::GraphQl);
+
+    // The owner isn't fetched with the articles, it can be an organization
+    let source = GraphQlSource::new(OWNER, REPOSITORY, &config).unwrap();
+    let owner = source.author(OWNER).await.unwrap();
+    assert_eq!(owner.login, OWNER);
+    assert_eq!(owner.name, "Clément Renault");
+    let requests = github.take_requests();
+    assert_eq!(requests, ["POST /graphql 200"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rebuild_with_the_cached_responses() {
+    let github = MockGitHub::start(default_issues()).await;
+    let config = github.config("cached", Api::Rest);
+
+    build_with_rest(&config).await;
+    github.take_requests();
+
+    // Nothing changed, GitHub answers with 304 Not Modified
+    let report = build_with_rest(&config).await;
+    assert_eq!(report.reused, 2);
+    let requests = github.take_requests();
+    assert!(requests.iter().all(|r| r.ends_with(" 304")), "{requests:#?}");
+
+    // Renaming an issue renders it again and creates a redirection
+    github.update_issues(|issues| {
+        let first = issues.iter_mut().find(|i| i.number == 1).unwrap();
+        first.previous_titles.push(first.title);
+        first.title = "My Renamed Article";
+        first.updated_at = "2024-04-02T10:00:00Z";
+    });
+    let report = build_with_rest(&config).await;
+    assert_eq!((report.rendered, report.reused), (2, 1));
+    assert!(read(&config.output, "my-first-article.html").contains("my-renamed-article"));
+    assert!(read(&config.output, "my-renamed-article.html").contains("My Renamed Article"));
+
+    // The profile of the author is displayed on all of their articles
+    github.update_bio("guest", "Writing about @tokio");
+    let report = build_with_rest(&config).await;
+    assert_eq!((report.rendered, report.reused), (2, 1));
+    let page = read(&config.output, "second-article.html");
+    assert!(page.contains("https://github.com/tokio"), "{page}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn replay_the_recorded_requests() {
+    let github = MockGitHub::start(default_issues()).await;
+    let mut recording = github.config("recording", Api::Rest);
+    let fixtures = recording.output.with_file_name("fixtures");
+    recording.fixtures = Fixtures::Record(fixtures.clone());
+    build_with_rest(&recording).await;
+    github.take_requests();
+
+    let mut replaying = github.config("replaying", Api::Rest);
+    replaying.fixtures = Fixtures::Replay(fixtures);
+    replaying.force = true;
+    let report = build_with_rest(&replaying).await;
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_default_blog(&replaying.output);
+    let requests = github.take_requests();
+    assert!(requests.is_empty(), "{requests:#?}");
+
+    // The previews are drawn with the recorded keys and profile pictures
+    let preview = "preview/second-article.png";
+    assert_eq!(
+        fs::read(replaying.output.join(preview)).unwrap(),
+        fs::read(recording.output.join(preview)).unwrap()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn only_build_one_article() {
+    let github = MockGitHub::start(default_issues()).await;
+    let mut config = github.config("only", Api::Rest);
+    build_with_rest(&config).await;
+
+    github.update_issues(|issues| {
+        let second = issues.iter_mut().find(|i| i.number == 2).unwrap();
+        second.body_html = "<p>The new content of the second article.</p>";
+        second.updated_at = "2024-04-02T10:00:00Z";
+    });
+    config.only = Some("second-article".to_string());
+    let report = build_with_rest(&config).await;
+    assert_eq!(report.rendered, 1);
+    assert!(read(&config.output, "second-article.html").contains("The new content"));
+
+    // The other articles are published as they were
+    let index = read(&config.output, "index.html");
+    assert!(index.contains("My First Article"), "{index}");
+    assert!(read(&config.output, "atom.xml").contains("my-first-article"));
+    assert!(config.output.join("preview/my-first-article.png").exists());
+    let manifest = read(&config.output, ".build-manifest.json");
+    assert!(manifest.contains("my-first-article"), "{manifest}");
+
+    // And a typo doesn't publish an empty blog
+    config.only = Some("tpyo".to_string());
+    let source = GitHubSource::new(OWNER, REPOSITORY, &config).unwrap();
+    let error = build(&source, &site(), &config).await.unwrap_err();
+    assert!(error.to_string().contains("no article has the \"tpyo\" slug"), "{error:#}");
+    assert!(read(&config.output, "index.html").contains("My First Article"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn keep_the_output_when_the_build_fails() {
+    let github = MockGitHub::start(default_issues()).await;
+    let config = github.config("failed", Api::Rest);
+    build_with_rest(&config).await;
+
+    let source = GitHubSource::new(OWNER, "unknown", &config).unwrap();
+    build(&source, &site(), &config).await.unwrap_err();
+    assert_default_blog(&config.output);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn build_into_an_output_with_a_trailing_slash() {
+    let github = MockGitHub::start(default_issues()).await;
+    let mut config = github.config("trailing-slash", Api::Rest);
+    let mut output = config.output.clone().into_os_string();
+    output.push("/");
+    config.output = output.into();
+
+    // The second build replaces the output of the first one
+    build_with_rest(&config).await;
+    let report = build_with_rest(&config).await;
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_default_blog(&config.output);
+    assert!(!config.output.join(".staging").exists());
+    assert!(!config.output.join(".previous").exists());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn refuse_to_replace_another_directory() {
+    let github = MockGitHub::start(default_issues()).await;
+    let config = github.config("refused", Api::Rest);
+    fs::create_dir_all(&config.output).unwrap();
+    fs::write(config.output.join("notes.txt"), "precious").unwrap();
+
+    let source = GitHubSource::new(OWNER, REPOSITORY, &config).unwrap();
+    let error = build(&source, &site(), &config).await.unwrap_err();
+    assert!(error.to_string().contains("refusing to replace"), "{error:#}");
+    assert_eq!(read(&config.output, "notes.txt"), "precious");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn report_the_missing_issues() {
+    let github = MockGitHub::start(default_issues()).await;
+    let mut config = github.config("missing", Api::Rest);
+    config.repository = Some(format!("{OWNER}/unknown"));
+
+    let source = GitHubSource::new(OWNER, "unknown", &config).unwrap();
+    let error = build(&source, &site(), &config).await.unwrap_err();
+    assert!(format!("{error:#}").contains("Not Found"), "{error:#}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn override_with_the_metadata_comment() {
+    let mut issues = default_issues();
+    let second = issues.iter_mut().find(|i| i.number == 2).unwrap();
+    second.body = concat!(
+        "<!-- meta: slug=custom-slug, date=2023-01-15, tags=rust, search, ",
+        "cover=https://example.com/cover.png, canonical=https://example.com/original -->\n",
+        "<!-- The synopsis of the second article -->\n\n![key]({images}/0.png)",
+    );
+    let github = MockGitHub::start(issues).await;
+    let config = github.config("metadata", Api::Rest);
+
+    let report = build_with_rest(&config).await;
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert!(read(&config.output, "second-article.html").contains("custom-slug"));
+    assert!(read(&config.output, "old-title.html").contains("custom-slug"));
+
+    let article = read(&config.output, "custom-slug.html");
+    assert!(article.contains("January 15, 2023"));
+    assert!(
+        article.contains(r#"<meta property="og:image" content="https://example.com/cover.png" />"#)
+    );
+    assert!(article.contains(r#"<link rel="canonical" href="https://example.com/original">"#));
+    assert!(read(&config.output, "index.html").contains("The synopsis of the second article"));
+
+    let feed = read(&config.output, "atom.xml");
+    assert!(feed.contains("<category>rust</category><category>search</category>"), "{feed}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn check_without_problems() {
+    let github = MockGitHub::start(default_issues()).await;
+    let config = github.config("check", Api::Rest);
+
+    // The first article has no synopsis, it is only a warning
+    let source = GitHubSource::new(OWNER, REPOSITORY, &config).unwrap();
+    let problems = check(&source).await.unwrap();
+    assert!(problems.is_empty(), "{problems:?}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn render_the_bodies_missing_from_the_api() {
+    let mut issues = default_issues();
+    let first = issues.iter_mut().find(|i| i.number == 1).unwrap();
+    first.body = "This is the first sentence of my blog post.\n\n## A Section\n\n    cargo run";
+    first.body_html = "";
+    let github = MockGitHub::start(issues).await;
+    let config = github.config("missing-body-html", Api::Rest);
+
+    let report = build_with_rest(&config).await;
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert!(report.warnings.contains(&"#1 has no `body_html`, rendering it locally".to_string()));
+    assert_default_blog(&config.output);
+    let page = read(&config.output, "my-first-article.html");
+    assert!(page.contains(r#"<code class="notranslate">cargo run"#), "{page}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn omit_the_raw_html_of_the_bodies_rendered_locally() {
+    let mut issues = default_issues();
+    let first = issues.iter_mut().find(|i| i.number == 1).unwrap();
+    first.body = "Hello <a><img src=\"file:///etc/hostname\"></a> ![x](file:///etc/hostname)\n\n\
+                  ```rust\nlet a = 1 < 2;\n```";
+    first.body_html = "";
+    let github = MockGitHub::start(issues).await;
+    let config = github.config("raw-html", Api::Rest);
+
+    let report = build_with_rest(&config).await;
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let page = read(&config.output, "my-first-article.html");
+    assert!(page.contains("Hello"), "{page}");
+    assert!(page.contains("<pre>let a = 1 &lt; 2;</pre>"), "{page}");
+    assert!(!page.contains("file:"), "{page}");
+    let images = fs::read_dir(config.output.join("assets/images")).unwrap();
+    assert_eq!(images.count(), 1, "only the image of the second article is downloaded");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn report_the_duplicate_slugs() {
+    let mut issues = default_issues();
+    let second = issues.iter_mut().find(|i| i.number == 2).unwrap();
+    second.body = "<!-- meta: slug=my-first-article -->\n\nThe second article.";
+    let github = MockGitHub::start(issues).await;
+    let config = github.config("duplicate-slugs", Api::Rest);
+
+    let report = build_with_rest(&config).await;
+    assert_eq!(report.rendered, 1);
+    let [error] = &report.errors[..] else { panic!("{:?}", report.errors) };
+    assert_eq!((error.number, error.slug.as_str()), (Some(2), "my-first-article"));
+    assert!(error.error.contains("already used by \"My First Article\""), "{}", error.error);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn report_the_invalid_images() {
+    let mut issues = default_issues();
+    let second = issues.iter_mut().find(|i| i.number == 2).unwrap();
+    second.body_html = r#"<p dir="auto"><a href="images/a.png"><img src="images/a.png"></a></p>"#;
+    let first = issues.iter_mut().find(|i| i.number == 1).unwrap();
+    first.body_html = r#"<p dir="auto">Without a source <a><img alt="x"></a></p>"#;
+    let github = MockGitHub::start(issues).await;
+    let config = github.config("invalid-images", Api::Rest);
+
+    let report = build_with_rest(&config).await;
+    assert_eq!(report.rendered, 1);
+    let [error] = &report.errors[..] else { panic!("{:?}", report.errors) };
+    assert_eq!(error.slug, "second-article");
+    assert!(error.error.contains("invalid image source \"images/a.png\""), "{}", error.error);
+    // The website misses an article, it doesn't replace the output
+    assert!(!config.output.exists());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn keep_the_output_when_an_article_fails() {
+    let github = MockGitHub::start(default_issues()).await;
+    let config = github.config("article-fails", Api::Rest);
+    build_with_rest(&config).await;
+
+    github.update_issues(|issues| {
+        let first = issues.iter_mut().find(|i| i.number == 1).unwrap();
+        first.body_html = r#"<p dir="auto"><a href="a.png"><img src="a.png"></a></p>"#;
+        first.updated_at = "2024-04-02T10:00:00Z";
+    });
+    let report = build_with_rest(&config).await;
+    assert_eq!(report.errors.len(), 1);
+    assert!(report.finish(&config).is_err());
+    assert_default_blog(&config.output);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn publish_the_previous_version_when_an_article_fails() {
+    let github = MockGitHub::start(default_issues()).await;
+    let mut config = github.config("keep-previous", Api::Rest);
+    config.on_article_error = ErrorPolicy::KeepPrevious;
+    build_with_rest(&config).await;
+    let manifest = |output: &Path| -> serde_json::Value {
+        serde_json::from_str(&read(output, ".build-manifest.json")).unwrap()
+    };
+    let previous_entry = manifest(&config.output)["articles"]["second-article"].clone();
+    assert!(previous_entry.is_object(), "{previous_entry}");
+
+    github.update_issues(|issues| {
+        let second = issues.iter_mut().find(|i| i.number == 2).unwrap();
+        second.body_html = r#"<p dir="auto"><a href="a.png"><img src="a.png"></a></p>"#;
+        second.updated_at = "2024-04-02T10:00:00Z";
+    });
+    let report = build_with_rest(&config).await;
+    let [error] = &report.errors[..] else { panic!("{:?}", report.errors) };
+    assert_eq!(error.slug, "second-article");
+    assert!(error.kept_previous);
+    report.finish(&config).unwrap();
+
+    // The previous page, with its preview and images, is still in the index and the feed
+    assert_default_blog(&config.output);
+    assert_eq!(manifest(&config.output)["articles"]["second-article"], previous_entry);
+}
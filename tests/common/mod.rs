@@ -0,0 +1,552 @@
+//! An in-process HTTP server mocking the subset of the GitHub API the build
+//! uses: repositories, issues, timelines, reactions, users, GraphQL and images,
+//! and the keys and profile pictures drawn on the previews.
+
+// Every test crate only uses some of the helpers
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use blog::{Api, Config, PreviewImages, Site};
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use url::Url;
+
+pub const OWNER: &str = "Kerollmops";
+pub const REPOSITORY: &str = "blog";
+pub const HOMEPAGE: &str = "https://blog.kerollmops.com/";
+
+/// The issues of the mocked repository, the most recent first.
+#[derive(Clone)]
+pub struct MockIssue {
+    pub number: u64,
+    pub title: &'static str,
+    pub body: &'static str,
+    /// The body rendered by GitHub, the `{images}` pattern
+    /// is replaced by the URL of the mocked image host.
+    pub body_html: &'static str,
+    pub author: &'static str,
+    pub comments: u32,
+    pub created_at: &'static str,
+    pub updated_at: &'static str,
+    pub previous_titles: Vec<&'static str>,
+    /// The date of the event labelling the issue as an article.
+    pub labelled_at: Option<&'static str>,
+    /// The reactions by GitHub name, like `+1` or `heart`.
+    pub reactions: Vec<&'static str>,
+    pub pull_request: bool,
+}
+
+/// The blog used by the integration tests.
+pub fn default_issues() -> Vec<MockIssue> {
+    vec![
+        MockIssue {
+            number: 3,
+            title: "Fix the build",
+            body: "A pull request",
+            body_html: "<p>A pull request</p>",
+            author: OWNER,
+            comments: 0,
+            created_at: "2024-04-01T10:00:00Z",
+            updated_at: "2024-04-01T10:00:00Z",
+            previous_titles: vec![],
+            labelled_at: None,
+            reactions: vec![],
+            pull_request: true,
+        },
+        MockIssue {
+            number: 2,
+            title: "Second Article",
+            body: "<!-- The synopsis of the second article -->\n\n![key]({images}/0.png)",
+            body_html: r#"<p dir="auto"><a href="{images}/0.png"><img src="{images}/0.png" alt="key"></a></p>"#,
+            author: "guest",
+            comments: 4,
+            created_at: "2024-03-20T10:00:00Z",
+            updated_at: "2024-03-21T10:00:00Z",
+            previous_titles: vec!["Old Title"],
+            labelled_at: Some("2024-03-22T10:00:00Z"),
+            reactions: vec!["heart", "+1", "+1"],
+            pull_request: false,
+        },
+        MockIssue {
+            number: 1,
+            title: "My First Article",
+            body: "This is the first sentence of my blog post.\n\n## A Section",
+            body_html: r#"<p dir="auto">This is the first sentence of my blog post.</p>
+<h2 dir="auto">A Section</h2>"#,
+            author: OWNER,
+            comments: 0,
+            created_at: "2024-03-10T10:00:00Z",
+            updated_at: "2024-03-10T10:00:00Z",
+            previous_titles: vec![],
+            labelled_at: None,
+            reactions: vec![],
+            pull_request: false,
+        },
+    ]
+}
+
+/// A mocked GitHub API, running until the end of the test.
+pub struct MockGitHub {
+    pub url: Url,
+    state: Arc<State>,
+}
+
+struct State {
+    url: Url,
+    issues: Mutex<Vec<MockIssue>>,
+    /// The bios replacing the default ones, by login.
+    bios: Mutex<HashMap<String, &'static str>>,
+    /// The requests received, like `GET /users/Kerollmops 200`.
+    requests: Mutex<Vec<String>>,
+}
+
+impl MockGitHub {
+    pub async fn start(issues: Vec<MockIssue>) -> MockGitHub {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let state = Arc::new(State {
+            url: url.clone(),
+            issues: Mutex::new(issues),
+            bios: Mutex::new(HashMap::new()),
+            requests: Mutex::new(Vec::new()),
+        });
+
+        let server_state = state.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let state = server_state.clone();
+                tokio::spawn(async move { handle_connection(stream, &state).await });
+            }
+        });
+
+        MockGitHub { url, state }
+    }
+
+    /// The requests received since the last call.
+    pub fn take_requests(&self) -> Vec<String> {
+        std::mem::take(&mut *self.state.requests.lock().unwrap())
+    }
+
+    /// Modifies the issues served by the API.
+    pub fn update_issues(&self, update: impl FnOnce(&mut Vec<MockIssue>)) {
+        update(&mut self.state.issues.lock().unwrap());
+    }
+
+    /// Changes the bio of a user.
+    pub fn update_bio(&self, login: &str, bio: &'static str) {
+        self.state.bios.lock().unwrap().insert(login.to_string(), bio);
+    }
+
+    /// Fetches the keys and the profile pictures drawn on the previews from this server.
+    fn preview_images(&self) -> PreviewImages {
+        let url = self.url.clone();
+        PreviewImages::new(move |image| {
+            let path = match image.split_once("/assets/keys/") {
+                Some((_, key)) => format!("/images/{key}"),
+                None => match image.strip_prefix("https://github.com/") {
+                    Some(avatar) => format!("/avatars/{avatar}"),
+                    None => panic!("the preview must not fetch {image}"),
+                },
+            };
+            url.join(&path).unwrap().to_string()
+        })
+    }
+
+    /// A configuration pointing to this server, writing everything in a new temporary directory.
+    pub fn config(&self, name: &str, api: Api) -> Config {
+        let directory = temporary_directory(name);
+        Config {
+            repository: Some(format!("{OWNER}/{REPOSITORY}")),
+            email_address: Some("kero@example.com".to_string()),
+            github_token: Some("a-token".to_string()),
+            homepage: Some(Url::parse(HOMEPAGE).unwrap()),
+            api,
+            api_url: self.url.clone(),
+            // We want to make sure we follow the pagination
+            per_page: 1,
+            output: directory.join("output"),
+            report: directory.join("build-report.json"),
+            cache: directory.join("cache"),
+            preview_images: self.preview_images(),
+            ..Config::default()
+        }
+    }
+}
+
+pub fn site() -> Site {
+    Site {
+        owner: OWNER.to_string(),
+        repository: REPOSITORY.to_string(),
+        email_address: "kero@example.com".to_string(),
+        homepage: Url::parse(HOMEPAGE).unwrap(),
+    }
+}
+
+/// A new empty directory, unique to the test.
+pub fn temporary_directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("blog-test-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+async fn handle_connection(mut stream: TcpStream, state: &State) {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 8 * 1024];
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await.unwrap();
+        if read == 0 {
+            return;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(position) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap().split(' ');
+    let (method, target) = (request_line.next().unwrap(), request_line.next().unwrap());
+    let headers: HashMap<_, _> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let length: usize = headers.get("content-length").map_or(0, |l| l.parse().unwrap());
+    while buffer.len() < header_end + length {
+        let read = stream.read(&mut chunk).await.unwrap();
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    let body = &buffer[header_end..header_end + length];
+
+    let url = state.url.join(target).unwrap();
+    let response = match method {
+        "POST" if url.path() == "/graphql" => {
+            graphql(state, serde_json::from_slice(body).unwrap()).map(Response::json)
+        }
+        "GET" => route(state, &url),
+        _ => None,
+    };
+    let mut response = response.unwrap_or_else(|| Response {
+        status: 404,
+        body: json!({ "message": "Not Found", "documentation_url": "" }).to_string().into_bytes(),
+        content_type: "application/json",
+        link: None,
+    });
+
+    // The responses are identified by the hash of their body
+    let mut hasher = DefaultHasher::new();
+    response.body.hash(&mut hasher);
+    let etag = format!("\"{:x}\"", hasher.finish());
+    if response.status == 200 && headers.get("if-none-match") == Some(&etag) {
+        response = Response { status: 304, body: Vec::new(), ..response };
+    }
+
+    let path = url[url::Position::BeforePath..].to_string();
+    state.requests.lock().unwrap().push(format!("{method} {path} {}", response.status));
+
+    let mut head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nETag: {etag}\r\n\
+         x-ratelimit-limit: 5000\r\nx-ratelimit-remaining: 4999\r\nx-ratelimit-reset: 1700000000\r\n\
+         Connection: close\r\n",
+        response.status,
+        response.content_type,
+        response.body.len(),
+    );
+    if let Some(link) = response.link {
+        head.push_str(&format!("Link: {link}\r\n"));
+    }
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes()).await.unwrap();
+    stream.write_all(&response.body).await.unwrap();
+    let _ = stream.shutdown().await;
+}
+
+struct Response {
+    status: u16,
+    body: Vec<u8>,
+    content_type: &'static str,
+    link: Option<String>,
+}
+
+impl Response {
+    fn json(value: Value) -> Response {
+        Response {
+            status: 200,
+            body: value.to_string().into_bytes(),
+            content_type: "application/json",
+            link: None,
+        }
+    }
+}
+
+fn route(state: &State, url: &Url) -> Option<Response> {
+    let segments: Vec<_> = url.path_segments()?.collect();
+    let issues = state.issues.lock().unwrap().clone();
+    let issue = |number: &str| issues.iter().find(|i| i.number.to_string() == number).cloned();
+
+    match segments.as_slice() {
+        ["images", name] => {
+            let body = std::fs::read(format!("assets/keys/{name}")).ok()?;
+            Some(Response { status: 200, body, content_type: "image/png", link: None })
+        }
+        ["avatars", _login] => {
+            let body = std::fs::read("tests/previews/avatar.png").ok()?;
+            Some(Response { status: 200, body, content_type: "image/png", link: None })
+        }
+        ["users", login] => Some(Response::json(user(state, login))),
+        ["repos", OWNER, REPOSITORY] => Some(Response::json(json!({
+            "id": 1,
+            "name": REPOSITORY,
+            "url": state.url.join("/repos/Kerollmops/blog").unwrap(),
+            "homepage": HOMEPAGE,
+        }))),
+        ["repos", OWNER, REPOSITORY, "issues"] => {
+            let items: Vec<_> = issues.iter().map(|i| rest_issue(state, i)).collect();
+            Some(paginate(url, items))
+        }
+        ["repos", OWNER, REPOSITORY, "issues", number, "timeline"] => {
+            let issue = issue(number)?;
+            let mut events: Vec<_> = issue
+                .previous_titles
+                .iter()
+                .map(|from| {
+                    json!({
+                        "event": "renamed",
+                        "created_at": issue.created_at,
+                        "rename": { "from": from, "to": issue.title },
+                    })
+                })
+                .collect();
+            events.extend(issue.labelled_at.map(|date| {
+                json!({
+                    "event": "labeled",
+                    "created_at": date,
+                    "label": { "name": "article", "color": "ededed" },
+                })
+            }));
+            Some(paginate(url, events))
+        }
+        ["repos", OWNER, REPOSITORY, "issues", number, "reactions"] => {
+            let reactions = issue(number)?
+                .reactions
+                .iter()
+                .enumerate()
+                .map(|(id, content)| {
+                    json!({
+                        "id": id,
+                        "node_id": "",
+                        "user": author(state, "guest"),
+                        "content": content,
+                        "created_at": "2024-03-25T10:00:00Z",
+                    })
+                })
+                .collect();
+            Some(paginate(url, reactions))
+        }
+        _ => None,
+    }
+}
+
+/// Serves the page requested with the `page` and `per_page`
+/// parameters and links to the next one like GitHub does.
+fn paginate(url: &Url, items: Vec<Value>) -> Response {
+    let param = |name| url.query_pairs().find(|(n, _)| n == name).map(|(_, v)| v.parse().unwrap());
+    let page: usize = param("page").unwrap_or(1);
+    let per_page: usize = param("per_page").unwrap_or(30);
+
+    let start = (page - 1) * per_page;
+    let page_items: Vec<_> = items.iter().skip(start).take(per_page).cloned().collect();
+    let link = (start + per_page < items.len()).then(|| {
+        let mut next = url.clone();
+        let pairs: Vec<_> = url.query_pairs().into_owned().filter(|(n, _)| n != "page").collect();
+        next.query_pairs_mut()
+            .clear()
+            .extend_pairs(pairs)
+            .append_pair("page", &(page + 1).to_string());
+        format!(r#"<{next}>; rel="next""#)
+    });
+
+    Response { link, ..Response::json(Value::Array(page_items)) }
+}
+
+fn user(state: &State, login: &str) -> Value {
+    let (name, bio) = match login {
+        OWNER => ("Clément Renault", "Working on @meilisearch"),
+        _ => ("A Guest", "Writing about @rust-lang"),
+    };
+    let bio = state.bios.lock().unwrap().get(login).copied().unwrap_or(bio);
+    let mut user = author(state, login);
+    user["name"] = json!(name);
+    user["bio"] = json!(bio);
+    user
+}
+
+fn author(state: &State, login: &str) -> Value {
+    let url = state.url.join(&format!("/users/{login}")).unwrap();
+    json!({
+        "login": login,
+        "id": 1,
+        "node_id": "",
+        "avatar_url": format!("https://avatars.githubusercontent.com/{login}"),
+        "gravatar_id": "",
+        "url": url,
+        "html_url": url,
+        "followers_url": url,
+        "following_url": url,
+        "gists_url": url,
+        "starred_url": url,
+        "subscriptions_url": url,
+        "organizations_url": url,
+        "repos_url": url,
+        "events_url": url,
+        "received_events_url": url,
+        "type": "User",
+        "site_admin": false,
+    })
+}
+
+fn body_html(state: &State, issue: &MockIssue) -> String {
+    issue.body_html.replace("{images}", state.url.join("/images").unwrap().as_str())
+}
+
+fn rest_issue(state: &State, issue: &MockIssue) -> Value {
+    let url =
+        state.url.join(&format!("/repos/{OWNER}/{REPOSITORY}/issues/{}", issue.number)).unwrap();
+    let mut value = json!({
+        "id": issue.number,
+        "node_id": "",
+        "url": url,
+        "repository_url": url,
+        "labels_url": url,
+        "comments_url": url,
+        "events_url": url,
+        "html_url": url,
+        "number": issue.number,
+        "state": "open",
+        "state_reason": null,
+        "title": issue.title,
+        "body": issue.body.replace("{images}", state.url.join("/images").unwrap().as_str()),
+        "body_html": body_html(state, issue),
+        "user": author(state, issue.author),
+        "labels": [{
+            "id": 1,
+            "node_id": "",
+            "url": url,
+            "name": "article",
+            "color": "ededed",
+            "default": false,
+        }],
+        "assignees": [],
+        "author_association": "OWNER",
+        "locked": false,
+        "comments": issue.comments,
+        "created_at": issue.created_at,
+        "updated_at": issue.updated_at,
+    });
+    // GitHub doesn't always render the body
+    if issue.body_html.is_empty() {
+        value["body_html"] = Value::Null;
+    }
+    if issue.pull_request {
+        value["pull_request"] =
+            json!({ "url": url, "html_url": url, "diff_url": url, "patch_url": url });
+    }
+    value
+}
+
+/// Answers the queries of the GraphQL source, recognized by the fields they ask for.
+fn graphql(state: &State, payload: Value) -> Option<Value> {
+    let query = payload["query"].as_str()?;
+    let variables = &payload["variables"];
+
+    if query.contains("homepageUrl") {
+        Some(json!({ "data": { "repository": { "homepageUrl": HOMEPAGE } } }))
+    } else if query.contains("repositoryOwner(login") {
+        let user = user(state, variables["login"].as_str()?);
+        Some(json!({ "data": { "repositoryOwner": {
+            "login": user["login"],
+            "avatarUrl": user["avatar_url"],
+            "name": user["name"],
+            "bio": user["bio"],
+        } } }))
+    } else if query.contains("issues(") {
+        // The GraphQL API never returns the pull requests as issues
+        let issues: Vec<_> =
+            state.issues.lock().unwrap().iter().filter(|i| !i.pull_request).cloned().collect();
+        let per_page = variables["perPage"].as_u64()? as usize;
+        let start: usize = variables["after"].as_str().map_or(0, |c| c.parse().unwrap());
+        let end = (start + per_page).min(issues.len());
+
+        let nodes: Vec<_> = issues[start..end].iter().map(|i| graphql_issue(state, i)).collect();
+        Some(json!({ "data": { "repository": { "issues": {
+            "pageInfo": { "hasNextPage": end < issues.len(), "endCursor": end.to_string() },
+            "nodes": nodes,
+        } } } }))
+    } else {
+        None
+    }
+}
+
+fn graphql_issue(state: &State, issue: &MockIssue) -> Value {
+    let user = user(state, issue.author);
+    let mut timeline: Vec<_> = issue
+        .previous_titles
+        .iter()
+        .map(|from| json!({ "__typename": "RenamedTitleEvent", "previousTitle": from }))
+        .collect();
+    timeline.extend(issue.labelled_at.map(|date| {
+        json!({ "__typename": "LabeledEvent", "createdAt": date, "label": { "name": "article" } })
+    }));
+    // The source only asks for the last 100 events
+    let has_previous_page = timeline.len() > 100;
+    let timeline = timeline.split_off(timeline.len().saturating_sub(100));
+
+    let mut groups: Vec<(&str, usize)> = Vec::new();
+    for reaction in &issue.reactions {
+        let content = match *reaction {
+            "+1" => "THUMBS_UP",
+            "-1" => "THUMBS_DOWN",
+            "heart" => "HEART",
+            "laugh" => "LAUGH",
+            "hooray" => "HOORAY",
+            "confused" => "CONFUSED",
+            "rocket" => "ROCKET",
+            _ => "EYES",
+        };
+        match groups.iter_mut().find(|(c, _)| *c == content) {
+            Some((_, count)) => *count += 1,
+            None => groups.push((content, 1)),
+        }
+    }
+
+    json!({
+        "number": issue.number,
+        "title": issue.title,
+        "body": issue.body.replace("{images}", state.url.join("/images").unwrap().as_str()),
+        "bodyHTML": body_html(state, issue),
+        "createdAt": issue.created_at,
+        "updatedAt": issue.updated_at,
+        "author": {
+            "login": user["login"],
+            "avatarUrl": user["avatar_url"],
+            "name": user["name"],
+            "bio": user["bio"],
+        },
+        "labels": { "nodes": [{ "name": "article" }] },
+        "comments": { "totalCount": issue.comments },
+        "reactionGroups": groups
+            .into_iter()
+            .map(|(content, count)| json!({ "content": content, "reactors": { "totalCount": count } }))
+            .collect::<Vec<_>>(),
+        "timelineItems": { "pageInfo": { "hasPreviousPage": has_previous_page }, "nodes": timeline },
+    })
+}
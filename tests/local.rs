@@ -0,0 +1,187 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use blog::{build, Api, Article, ArticleSource, LocalSource};
+use common::{default_issues, site, temporary_directory, MockGitHub};
+use url::Url;
+
+/// Writes the posts into a new directory and reads them with a [`LocalSource`].
+async fn read_posts(name: &str, posts: &[(&str, &str)]) -> anyhow::Result<Vec<Article>> {
+    let directory = temporary_directory(name);
+    for (file_name, content) in posts {
+        fs::write(directory.join(file_name), content).unwrap();
+    }
+    LocalSource::new(&directory, "Kerollmops")?.articles().await
+}
+
+#[tokio::test]
+async fn yaml_and_toml_front_matter() {
+    let yaml = concat!(
+        "---\ntitle: In YAML\ndate: 2024-03-10\nauthor: guest\nlabels: [article, rust]\n",
+        "synopsis: The synopsis\nslug: in-yaml\ntoc: true\nissue: 42\n---\n\nThe body.\n",
+    );
+    let toml = "+++\ntitle = \"In TOML\"\ndate = 2024-03-12T10:00:00Z\n+++\nThe body.\n";
+    let articles =
+        read_posts("front-matter", &[("a.md", yaml), ("b.md", toml), ("c.txt", "ignored")])
+            .await
+            .unwrap();
+
+    // The most recent first, like the GitHub issues
+    let [toml, yaml] = &articles[..] else { panic!("expected two articles") };
+    assert_eq!(
+        (toml.title.as_str(), toml.slug(), toml.number),
+        ("In TOML", "in-toml".into(), None)
+    );
+    assert_eq!(toml.author, "Kerollmops");
+    assert_eq!(toml.created_at.to_rfc3339(), "2024-03-12T10:00:00+00:00");
+    assert_eq!(toml.body, "The body.\n");
+
+    assert_eq!(
+        (yaml.slug(), yaml.number, yaml.author.as_str()),
+        ("in-yaml".into(), Some(42), "guest")
+    );
+    assert_eq!(yaml.labels, ["article", "rust"]);
+    assert_eq!(yaml.synopsis.as_deref(), Some("The synopsis"));
+    assert_eq!(yaml.toc, Some(true));
+    assert_eq!(yaml.created_at.to_rfc3339(), "2024-03-10T00:00:00+00:00");
+    assert_eq!(yaml.body, "The body.\n");
+}
+
+#[tokio::test]
+async fn invalid_front_matter() {
+    let errors = [
+        ("missing", "title: No Front Matter\n"),
+        ("unclosed", "---\ntitle: Unclosed\ndate: 2024-03-10\n"),
+        ("date", "---\ntitle: Bad Date\ndate: yesterday\n---\n"),
+        ("title", "---\ndate: 2024-03-10\n---\n"),
+        ("slug", "---\ntitle: Escaping\ndate: 2024-03-10\nslug: ../escaped\n---\n"),
+    ];
+    for (name, content) in errors {
+        let error = read_posts(&format!("invalid-{name}"), &[("post.md", content)]).await;
+        assert!(error.is_err(), "the {name} front matter must be rejected");
+    }
+}
+
+#[tokio::test]
+async fn render_like_github() {
+    let content = concat!(
+        "---\ntitle: Rendering\ndate: 2024-03-10\n---\n\n",
+        "<!-- The synopsis -->\n\n",
+        "## A Heading\n\n",
+        "| a | b |\n|---|---|\n| 1 | 2 |\n\n",
+        "- [x] done\n- [ ] todo\n\n",
+        "A note[^1].\n\n[^1]: The footnote.\n\n",
+        "> [!NOTE]\n> An alert.\n\n",
+        "```rust\nfn main() {}\n```\n\n",
+        "```\nplain\n```\n\n",
+        "![local](images/a.png) ![remote](https://example.com/b.png)\n",
+    );
+    let directory = temporary_directory("render");
+    fs::write(directory.join("post.md"), content).unwrap();
+    let mut articles =
+        LocalSource::new(&directory, "Kerollmops").unwrap().articles().await.unwrap();
+    let html = articles.remove(0).body_html.unwrap();
+
+    assert!(!html.contains("The synopsis"), "the comments are removed");
+    assert!(html.contains(r#"<h2 dir="auto">A Heading</h2>"#));
+    assert!(html.contains(r#"<table role="table">"#));
+    assert!(html.contains(r#"<ul class="contains-task-list">"#));
+    assert!(html.contains(r#"<input type="checkbox" class="task-list-item-checkbox" checked="""#));
+    assert!(html.contains(r##"<a href="#fn-1" id="fnref-1" data-footnote-ref>1</a>"##));
+    assert!(html.contains(r#"<section class="footnotes" data-footnotes>"#));
+    assert!(html.contains(r#"<div class="markdown-alert markdown-alert-note">"#));
+    assert!(html.contains(concat!(
+        r#"<div class="highlight highlight-source-rust notranslate position-relative "#,
+        r#"overflow-auto" dir="auto"><pre>fn main() {}</pre></div>"#,
+    )));
+    assert!(html.contains(r#"<pre class="notranslate"><code class="notranslate">plain"#));
+
+    // The images are wrapped into links and the relative ones resolved next to the post
+    let local = Url::from_file_path(fs::canonicalize(directory).unwrap().join("images/a.png"));
+    let local = local.unwrap();
+    assert!(html.contains(&format!(r#"<a href="{local}"><img src="{local}" alt="local""#)));
+    assert!(html
+        .contains(r#"<a href="https://example.com/b.png"><img src="https://example.com/b.png""#));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn build_with_relative_images() {
+    let github = MockGitHub::start(default_issues()).await;
+    let config = github.config("local-images", Api::Rest);
+    let directory = temporary_directory("local-images-posts");
+    fs::create_dir_all(directory.join("images")).unwrap();
+    fs::copy("assets/keys/0.png", directory.join("images/key.png")).unwrap();
+    fs::write(
+        directory.join("post.md"),
+        "---\ntitle: With an Image\ndate: 2024-03-10\n---\n\n![key](images/key.png)\n",
+    )
+    .unwrap();
+
+    let source = LocalSource::new(&directory, "Kerollmops").unwrap();
+    let report = build(&source, &site(), &config).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+
+    let page = fs::read_to_string(config.output.join("with-an-image.html")).unwrap();
+    let image = page.split("assets/images/").nth(1).unwrap().split('"').next().unwrap();
+    let copied = fs::read(Path::new(&config.output).join("assets/images").join(image)).unwrap();
+    assert_eq!(copied, fs::read("assets/keys/0.png").unwrap());
+
+    // Editing the image publishes it again, even if the post didn't change
+    fs::copy("assets/keys/1.png", directory.join("images/key.png")).unwrap();
+    let report = build(&source, &site(), &config).await.unwrap();
+    assert_eq!((report.rendered, report.reused), (1, 0));
+    let copied = fs::read(Path::new(&config.output).join("assets/images").join(image)).unwrap();
+    assert_eq!(copied, fs::read("assets/keys/1.png").unwrap());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn hide_the_comments_without_an_issue() {
+    let github = MockGitHub::start(default_issues()).await;
+    let config = github.config("local-without-issue", Api::Rest);
+    let directory = temporary_directory("local-without-issue-posts");
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("a.md"), "---\ntitle: Without Issue\ndate: 2024-03-10\n---\n\nA.\n")
+        .unwrap();
+    fs::write(
+        directory.join("b.md"),
+        "---\ntitle: With an Issue\ndate: 2024-03-11\nissue: 7\n---\n\nB.\n",
+    )
+    .unwrap();
+
+    let source = LocalSource::new(&directory, "Kerollmops").unwrap();
+    let report = build(&source, &site(), &config).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+
+    let page = fs::read_to_string(config.output.join("without-issue.html")).unwrap();
+    assert!(!page.contains("/issues/"), "{page}");
+    assert!(!page.contains("tiny-utterances\""), "{page}");
+    let page = fs::read_to_string(config.output.join("with-an-issue.html")).unwrap();
+    assert!(page.contains("/issues/7#comment-composer-heading"), "{page}");
+    assert!(page.contains(r#"data-issue-number="7""#), "{page}");
+    // The comments and reactions of the issue are unknown
+    assert!(!page.contains("0 comments"), "{page}");
+    assert!(page.contains("Join the discussion"), "{page}");
+    let index = fs::read_to_string(config.output.join("index.html")).unwrap();
+    assert!(!index.contains("comments"), "{index}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn refuse_the_images_outside_of_the_posts() {
+    let github = MockGitHub::start(default_issues()).await;
+    let config = github.config("outside-images", Api::Rest);
+    let directory = temporary_directory("outside-images-posts");
+    fs::create_dir_all(directory.join("posts")).unwrap();
+    fs::copy("assets/keys/0.png", directory.join("secret.png")).unwrap();
+    fs::write(
+        directory.join("posts/post.md"),
+        "---\ntitle: With an Image\ndate: 2024-03-10\n---\n\n![key](../secret.png)\n",
+    )
+    .unwrap();
+
+    let source = LocalSource::new(directory.join("posts"), "Kerollmops").unwrap();
+    let report = build(&source, &site(), &config).await.unwrap();
+    let [error] = &report.errors[..] else { panic!("{:?}", report.errors) };
+    assert!(error.error.contains("not in the directory of the posts"), "{}", error.error);
+}
@@ -0,0 +1,50 @@
+use blog::Metadata;
+
+#[test]
+fn metadata_on_one_line() {
+    let body = concat!(
+        "<!-- The synopsis -->\n",
+        "<!-- meta: slug=my-article, date=2024-03-10, tags=rust, search,",
+        " canonical=https://example.com/original -->\n\nThe body.",
+    );
+    let metadata = Metadata::from_body(body).unwrap();
+    assert_eq!(metadata.slug.as_deref(), Some("my-article"));
+    assert_eq!(metadata.date.unwrap().to_rfc3339(), "2024-03-10T00:00:00+00:00");
+    assert_eq!(metadata.tags, ["rust", "search"]);
+    assert_eq!(metadata.cover, None);
+    assert_eq!(metadata.canonical.unwrap().as_str(), "https://example.com/original");
+}
+
+#[test]
+fn metadata_in_yaml() {
+    let body = concat!(
+        "<!-- meta:\n",
+        "date: 2024-03-10T12:30:00+01:00\n",
+        "tags: [rust, search]\n",
+        "cover: https://example.com/cover.png\n",
+        "-->\n\nThe body.",
+    );
+    let metadata = Metadata::from_body(body).unwrap();
+    assert_eq!(metadata.slug, None);
+    assert_eq!(metadata.date.unwrap().to_rfc3339(), "2024-03-10T11:30:00+00:00");
+    assert_eq!(metadata.tags, ["rust", "search"]);
+    assert_eq!(metadata.cover.unwrap().as_str(), "https://example.com/cover.png");
+}
+
+#[test]
+fn invalid_metadata() {
+    assert!(Metadata::from_body("The body without comment.").unwrap().slug.is_none());
+    assert!(Metadata::from_body("<!-- meta: slug -->").is_err());
+    assert!(Metadata::from_body("<!-- meta: author=someone -->").is_err());
+    assert!(Metadata::from_body("<!-- meta: date=yesterday -->").is_err());
+}
+
+#[test]
+fn reject_the_unsafe_slugs() {
+    for slug in ["../escaped", "Upper", "a/b", "trailing-", "index", "atom", "assets"] {
+        let body = format!("<!-- meta: slug={slug} -->");
+        assert!(Metadata::from_body(&body).is_err(), "{slug} must be rejected");
+    }
+    let metadata = Metadata::from_body("<!-- meta: slug=my-2nd-article -->").unwrap();
+    assert_eq!(metadata.slug.as_deref(), Some("my-2nd-article"));
+}
@@ -0,0 +1,161 @@
+//! Renders the previews with local images and compares them with the reference
+//! PNGs committed in `tests/previews`. A small difference is tolerated as the
+//! anti-aliasing can vary between platforms. On failure, the rendering and an
+//! image highlighting the differences in red are written next to the reference.
+//!
+//! Run the tests with `UPDATE_SNAPSHOTS=1` to accept the new renderings.
+
+use std::path::Path;
+
+use blog::Preview;
+use tiny_skia::Pixmap;
+
+/// How much a channel of a pixel can differ before the pixel is considered different.
+const CHANNEL_TOLERANCE: u8 = 16;
+
+/// The ratio of different pixels above which the images are considered different.
+const MAX_DIFFERENT_PIXELS: f64 = 0.001;
+
+/// Serves the keys from the assets and a fixed avatar instead of the one on GitHub.
+fn local_png(url: &str) -> Option<Vec<u8>> {
+    let path = match url.split_once("/assets/keys/") {
+        Some((_, key)) => Path::new("assets/keys").join(key),
+        None if url.starts_with("https://github.com/") => "tests/previews/avatar.png".into(),
+        None => panic!("the preview must not fetch {url}"),
+    };
+    Some(std::fs::read(&path).unwrap_or_else(|e| panic!("while reading {}: {e}", path.display())))
+}
+
+/// The text of the previews is drawn with the `Inter.ttc` embedded in the binary, any
+/// other font matches nothing and the previews would be compared without their text.
+fn assert_inter_font() {
+    let mut fonts = usvg::fontdb::Database::new();
+    fonts.load_font_data(std::fs::read("Inter.ttc").unwrap());
+    let families: Vec<_> =
+        fonts.faces().flat_map(|face| face.families.iter().map(|(name, _)| name)).collect();
+    assert!(
+        families.iter().any(|name| *name == "Inter"),
+        "Inter.ttc doesn't contain the Inter font but {families:?}, no text would be drawn",
+    );
+}
+
+/// The same preview drawn with a blank title, an empty one can't be cut into lines.
+fn untitled(preview: &Preview) -> Preview {
+    Preview {
+        username: preview.username.clone(),
+        publish_date: preview.publish_date.clone(),
+        title: " ".to_string(),
+        comment_count: preview.comment_count,
+        domain: preview.domain.clone(),
+    }
+}
+
+/// A reference rendered without the font would only contain the background and the
+/// images, it must differ from the same preview drawn without a title.
+fn assert_title_drawn(path: &Path, png: &[u8], untitled_png: &[u8], title: &str) {
+    let image = Pixmap::decode_png(png).unwrap();
+    let (_, different) = diff_images(&image, &Pixmap::decode_png(untitled_png).unwrap());
+    let ratio = different as f64 / (image.width() * image.height()) as f64;
+    assert!(
+        ratio > MAX_DIFFERENT_PIXELS,
+        "{} doesn't contain the title {title:?}, it must be rendered again with the Inter font",
+        path.display(),
+    );
+}
+
+fn assert_preview(name: &str, preview: Preview) {
+    assert_inter_font();
+    let title = preview.title.clone();
+    let untitled_png = untitled(&preview).generate_png_with(local_png).unwrap();
+    let rendered = preview.generate_png_with(local_png).unwrap();
+    let directory = Path::new("tests/previews");
+    let path = directory.join(format!("{name}.png"));
+    let actual_path = directory.join(format!("{name}.actual.png"));
+    let diff_path = directory.join(format!("{name}.diff.png"));
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        assert_title_drawn(&path, &rendered, &untitled_png, &title);
+        std::fs::write(&path, &rendered).unwrap();
+        return;
+    }
+    let expected = std::fs::read(&path).unwrap_or_else(|e| {
+        std::fs::write(&actual_path, &rendered).unwrap();
+        panic!(
+            "while reading {}: {e}, see {} and run the tests \
+             with UPDATE_SNAPSHOTS=1 to accept the rendering",
+            path.display(),
+            actual_path.display(),
+        )
+    });
+    assert_title_drawn(&path, &expected, &untitled_png, &title);
+
+    let actual = Pixmap::decode_png(&rendered).unwrap();
+    let expected = Pixmap::decode_png(&expected).unwrap();
+    assert_eq!((actual.width(), actual.height()), (expected.width(), expected.height()));
+
+    let (diff, different) = diff_images(&expected, &actual);
+    let ratio = different as f64 / (actual.width() * actual.height()) as f64;
+    if ratio <= MAX_DIFFERENT_PIXELS {
+        let _ = std::fs::remove_file(&actual_path);
+        let _ = std::fs::remove_file(&diff_path);
+        return;
+    }
+
+    std::fs::write(&actual_path, &rendered).unwrap();
+    diff.save_png(&diff_path).unwrap();
+    panic!(
+        "{different} pixels ({:.2}%) of {} differ, see {} and {}, \
+         run the tests with UPDATE_SNAPSHOTS=1 to accept the rendering",
+        ratio * 100.0,
+        path.display(),
+        actual_path.display(),
+        diff_path.display(),
+    );
+}
+
+/// Returns the expected image faded, with the different pixels in red, and their number.
+fn diff_images(expected: &Pixmap, actual: &Pixmap) -> (Pixmap, usize) {
+    let mut diff = Pixmap::new(expected.width(), expected.height()).unwrap();
+    let mut different = 0;
+
+    let pixels = expected.data().chunks(4).zip(actual.data().chunks(4));
+    for (output, (e, a)) in diff.data_mut().chunks_mut(4).zip(pixels) {
+        if e.iter().zip(a).any(|(e, a)| e.abs_diff(*a) > CHANNEL_TOLERANCE) {
+            different += 1;
+            output.copy_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let gray = ((e[0] as u16 + e[1] as u16 + e[2] as u16) / 3) as u8;
+            let faded = 255 - (255 - gray) / 4;
+            output.copy_from_slice(&[faded, faded, faded, 255]);
+        }
+    }
+
+    (diff, different)
+}
+
+fn preview(username: &str, title: &str, comment_count: u32) -> Preview {
+    Preview {
+        username: username.to_string(),
+        publish_date: "March 10, 2024".to_string(),
+        title: title.to_string(),
+        comment_count: Some(comment_count),
+        domain: "blog.kerollmops.com".to_string(),
+    }
+}
+
+#[test]
+fn preview_with_a_one_line_title() {
+    assert_preview("one-line", preview("Kerollmops", "A Short Title", 1));
+}
+
+#[test]
+fn preview_with_a_two_lines_title() {
+    let title = "A Title Long Enough to Be Cut in Two Lines";
+    assert_preview("two-lines", preview("Kerollmops", title, 0));
+}
+
+#[test]
+fn preview_with_a_three_lines_title_by_a_guest() {
+    let title = "A Very Long Title That Needs to Be Cut in Three Lines to Fit";
+    assert_preview("three-lines-guest", preview("a-guest-author", title, 42));
+}
@@ -0,0 +1,66 @@
+use blog::Article;
+use chrono::{TimeZone, Utc};
+
+fn article(body: &str, body_html: &str) -> Article {
+    let date = Utc.with_ymd_and_hms(2024, 3, 10, 0, 0, 0).unwrap();
+    Article {
+        number: Some(1),
+        title: "My First Article".to_string(),
+        slug: None,
+        author: "Kerollmops".to_string(),
+        labels: vec!["article".to_string()],
+        synopsis: None,
+        toc: None,
+        metadata: Default::default(),
+        body: body.to_string(),
+        body_html: Some(body_html.to_string()),
+        created_at: date,
+        updated_at: date,
+        comments_count: Some(0),
+    }
+}
+
+#[test]
+fn explicit_synopsis_first() {
+    let html = "<p>The first paragraph.</p>";
+    let mut article = article("<!-- The synopsis -->\n\nThe first paragraph.", html);
+    assert_eq!(article.synopsis_or_first_paragraph(200), " The synopsis ");
+
+    article.synopsis = Some("From the front matter".to_string());
+    assert_eq!(article.synopsis_or_first_paragraph(200), "From the front matter");
+}
+
+#[test]
+fn first_paragraph_as_plain_text() {
+    let html = concat!(
+        r#"<h2 dir="auto">Title</h2><p dir="auto"></p>"#,
+        r#"<p dir="auto">Some <code>code</code> and a <a href="/">link</a>,"#,
+        "\n",
+        r#"on two lines.</p><p>The second paragraph.</p>"#,
+    );
+    let article = article("", html);
+    assert_eq!(article.explicit_synopsis(), None);
+    assert_eq!(article.synopsis_or_first_paragraph(200), "Some code and a link, on two lines.");
+}
+
+#[test]
+fn first_paragraph_escaped() {
+    let html = r#"<p>Using <code>Vec&lt;T&gt;</code> with "quotes" &amp; more.</p>"#;
+    let article = article("", html);
+    assert_eq!(
+        article.synopsis_or_first_paragraph(200),
+        "Using Vec&lt;T&gt; with &quot;quotes&quot; &amp; more."
+    );
+}
+
+#[test]
+fn first_paragraph_cut_at_a_sentence() {
+    let html = "<p>A first sentence. Is it the second one? Yes! A much longer last sentence.</p>";
+    let article = article("", html);
+    assert_eq!(
+        article.synopsis_or_first_paragraph(45),
+        "A first sentence. Is it the second one? Yes!"
+    );
+    assert_eq!(article.synopsis_or_first_paragraph(30), "A first sentence.");
+    assert_eq!(article.synopsis_or_first_paragraph(12), "A first…");
+}
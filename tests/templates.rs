@@ -0,0 +1,209 @@
+//! Renders every template from representative data and compares the result with
+//! the snapshots committed in `tests/snapshots`, so that template changes show up
+//! as reviewable diffs.
+//!
+//! Run the tests with `UPDATE_SNAPSHOTS=1` to accept the new renderings.
+
+use std::path::Path;
+
+use askama::Template;
+use blog::{
+    ArticleInList, ArticleTemplate, IndexTemplate, Preview, ReactionCounts, RedirectTemplate,
+};
+use url::Url;
+
+/// Compares the rendering with the snapshot, writes the new rendering next to it
+/// and fails when they differ or the snapshot is missing, unless `UPDATE_SNAPSHOTS`
+/// is set in which case the snapshot is replaced by the new rendering.
+fn assert_snapshot(name: &str, rendered: &str) {
+    let path = Path::new("tests/snapshots").join(name);
+    let new_path = path.with_extension(format!("{}.new", extension(&path)));
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+
+    match std::fs::read_to_string(&path) {
+        Ok(expected) if expected == rendered => {
+            let _ = std::fs::remove_file(&new_path);
+        }
+        _ if update => {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, rendered).unwrap();
+            let _ = std::fs::remove_file(&new_path);
+        }
+        Ok(_) => {
+            std::fs::write(&new_path, rendered).unwrap();
+            panic!(
+                "{} doesn't match the rendering written into {}, \
+                 run the tests with UPDATE_SNAPSHOTS=1 to accept it",
+                path.display(),
+                new_path.display(),
+            );
+        }
+        Err(e) => {
+            std::fs::write(&new_path, rendered).unwrap();
+            panic!(
+                "while reading {}: {e}, the rendering is written into {}, \
+                 run the tests with UPDATE_SNAPSHOTS=1 to accept it",
+                path.display(),
+                new_path.display(),
+            );
+        }
+    }
+}
+
+fn extension(path: &Path) -> &str {
+    path.extension().and_then(|e| e.to_str()).unwrap_or_default()
+}
+
+fn profil_picture_url() -> Url {
+    Url::parse("https://avatars.githubusercontent.com/u/3610253?v=4").unwrap()
+}
+
+const HTML_BIO: &str = r#"Working on <a href="https://github.com/meilisearch">@meilisearch</a>"#;
+
+fn index(articles: Vec<ArticleInList>) -> IndexTemplate {
+    IndexTemplate {
+        profil_picture_url: profil_picture_url(),
+        username: "Kerollmops".to_string(),
+        description: "The blog of Kerollmops".to_string(),
+        url: Url::parse("https://blog.kerollmops.com/").unwrap(),
+        preview_url: "https://blog.kerollmops.com/preview/homepage.png".to_string(),
+        html_bio: HTML_BIO.to_string(),
+        articles,
+        plausible_domain: Some("blog.kerollmops.com".to_string()),
+    }
+}
+
+fn article(reaction_counts: ReactionCounts) -> ArticleTemplate {
+    ArticleTemplate {
+        profil_picture_url: profil_picture_url(),
+        username: "Kerollmops".to_string(),
+        owner: "Kerollmops".to_string(),
+        repository: "blog".to_string(),
+        issue_number: Some(42),
+        html_bio: HTML_BIO.to_string(),
+        url: "https://blog.kerollmops.com/my-first-article".to_string(),
+        publish_date: "March 10, 2024".to_string(),
+        title: "My First Article".to_string(),
+        description: "This is the first sentence of my blog post.".to_string(),
+        html_content: "<p>This is the first sentence of my blog post.</p>\n<h2>A Section</h2>"
+            .to_string(),
+        table_of_contents: None,
+        canonical_url: None,
+        preview_url: "https://blog.kerollmops.com/preview/my-first-article.png".to_string(),
+        comments_count: Some(3),
+        reaction_counts: Some(reaction_counts),
+        plausible_domain: None,
+    }
+}
+
+fn preview(title: &str, comment_count: u32) -> Preview {
+    Preview {
+        username: "Kerollmops".to_string(),
+        publish_date: "March 10, 2024".to_string(),
+        title: title.to_string(),
+        comment_count: Some(comment_count),
+        domain: "blog.kerollmops.com".to_string(),
+    }
+}
+
+#[test]
+fn index_with_guest_authors() {
+    let articles = vec![
+        ArticleInList {
+            title: "Written by a Guest".to_string(),
+            synopsis: "An article written by someone else.".to_string(),
+            url: "written-by-a-guest".to_string(),
+            publish_date: "March 22, 2024".to_string(),
+            guest_user: Some("guest".to_string()),
+            comments_count: Some(4),
+        },
+        ArticleInList {
+            title: "My First Article".to_string(),
+            synopsis: "This is the first sentence of my blog post.".to_string(),
+            url: "my-first-article".to_string(),
+            publish_date: "March 10, 2024".to_string(),
+            guest_user: None,
+            comments_count: Some(0),
+        },
+    ];
+    assert_snapshot("index.html", &index(articles).render().unwrap());
+}
+
+#[test]
+fn index_without_articles() {
+    let mut template = index(Vec::new());
+    template.plausible_domain = None;
+    assert_snapshot("index-empty.html", &template.render().unwrap());
+}
+
+#[test]
+fn article_without_reactions() {
+    let template = article(ReactionCounts::default());
+    assert_snapshot("article-no-reactions.html", &template.render().unwrap());
+}
+
+#[test]
+fn article_with_all_reactions() {
+    let mut template = article(ReactionCounts {
+        heart: 1,
+        plus_one: 2,
+        laugh: 3,
+        confused: 4,
+        hooray: 5,
+        minus_one: 6,
+        rocket: 7,
+        eyes: 8,
+    });
+    template.plausible_domain = Some("blog.kerollmops.com".to_string());
+    assert_snapshot("article-all-reactions.html", &template.render().unwrap());
+}
+
+#[test]
+fn article_without_counts() {
+    let mut template = article(ReactionCounts::default());
+    template.comments_count = None;
+    template.reaction_counts = None;
+    assert_snapshot("article-no-counts.html", &template.render().unwrap());
+}
+
+#[test]
+fn article_with_a_table_of_contents() {
+    let mut template = article(ReactionCounts::default());
+    template.table_of_contents = Some(
+        r##"<ul><li><a href="#a-section">A Section</a><ul><li><a href="#details">Details</a></li></ul></li></ul>"##
+            .to_string(),
+    );
+    assert_snapshot("article-table-of-contents.html", &template.render().unwrap());
+}
+
+#[test]
+fn redirect() {
+    let template = RedirectTemplate { redirect_url: "my-first-article".to_string() };
+    assert_snapshot("redirect.html", &template.render().unwrap());
+}
+
+#[test]
+fn preview_with_a_one_line_title() {
+    assert_snapshot("preview-one-line.svg", &preview("A Short Title", 1).svg());
+}
+
+#[test]
+fn preview_with_a_two_lines_title() {
+    let title = "A Title Long Enough to Be Cut in Two Lines";
+    assert_snapshot("preview-two-lines.svg", &preview(title, 0).svg());
+}
+
+#[test]
+fn preview_with_a_three_lines_title() {
+    let title = "A Very Long Title That Needs to Be Cut in Three Lines to Fit";
+    assert_snapshot("preview-three-lines.svg", &preview(title, 12).svg());
+}
+
+#[test]
+fn preview_with_a_too_long_title() {
+    let title = concat!(
+        "A Title So Long That Even Three Lines Are Not ",
+        "Enough to Display It Entirely, It Must Be Cut",
+    );
+    assert_snapshot("preview-ellipsis.svg", &preview(title, 3).svg());
+}
@@ -0,0 +1,111 @@
+use blog::{HeadingAnchors, HtmlTransform, TableClasses, TableOfContents, Transforms};
+use kuchiki::NodeRef;
+
+fn apply(transform: impl HtmlTransform + 'static, html: &str) -> String {
+    let mut transforms = Transforms::empty();
+    transforms.register(transform);
+    transforms.apply(html).unwrap()
+}
+
+#[test]
+fn anchors_in_multiline_and_nested_headings() {
+    let html = "<h2 dir=\"auto\">A <em>nested</em>\nheading</h2>\n<h3>Plain</h3><h5>Ignored</h5>";
+    assert_eq!(
+        apply(HeadingAnchors, html),
+        "<h2 dir=\"auto\" id=\"a-nested-heading\"><a href=\"#a-nested-heading\">\
+         A <em>nested</em>\nheading</a></h2>\n\
+         <h3 id=\"plain\"><a href=\"#plain\">Plain</a></h3><h5 id=\"ignored\">Ignored</h5>"
+    );
+}
+
+#[test]
+fn unique_anchors_from_the_text() {
+    let html = concat!(
+        "<h2>Benchmarks</h2><h3>The <code>Vec::push</code> method</h3>",
+        "<h2>Benchmarks</h2><h2>Benchmarks 1</h2><h2>Benchmarks</h2><h4>?!</h4>",
+    );
+    assert_eq!(
+        apply(HeadingAnchors, html),
+        concat!(
+            r##"<h2 id="benchmarks"><a href="#benchmarks">Benchmarks</a></h2>"##,
+            r##"<h3 id="the-vecpush-method"><a href="#the-vecpush-method">"##,
+            r##"The <code>Vec::push</code> method</a></h3>"##,
+            r##"<h2 id="benchmarks-1"><a href="#benchmarks-1">Benchmarks</a></h2>"##,
+            r##"<h2 id="benchmarks-1-1"><a href="#benchmarks-1-1">Benchmarks 1</a></h2>"##,
+            r##"<h2 id="benchmarks-2"><a href="#benchmarks-2">Benchmarks</a></h2>"##,
+            r##"<h4 id="section"><a href="#section">?!</a></h4>"##,
+        )
+    );
+}
+
+#[test]
+fn anchors_compatible_with_github() {
+    let html = concat!(
+        r##"<div class="markdown-heading"><h2 class="heading-element">Setup</h2>"##,
+        r##"<a id="user-content-setup" class="anchor" href="#setup"></a></div>"##,
+        r##"<p><a href="#user-content-setup">see</a> <a href="#user-content-fn-1">1</a></p>"##,
+    );
+    assert_eq!(
+        apply(HeadingAnchors, html),
+        concat!(
+            r##"<h2 class="heading-element" id="setup"><a href="#setup">Setup</a></h2>"##,
+            r##"<p><a href="#setup">see</a> <a href="#user-content-fn-1">1</a></p>"##,
+        )
+    );
+}
+
+#[test]
+fn table_classes_whatever_the_attributes() {
+    let html = r#"<table class="wide" role="table"></table><table></table>"#;
+    assert_eq!(
+        apply(TableClasses, html),
+        r#"<table class="wide table table-striped" role="table"></table><table class="table table-striped"></table>"#
+    );
+}
+
+#[test]
+fn register_a_transform() {
+    struct LazyImages;
+
+    impl HtmlTransform for LazyImages {
+        fn name(&self) -> &'static str {
+            "lazy images"
+        }
+
+        fn transform(&self, root: &NodeRef) -> anyhow::Result<()> {
+            for image in root.select("img").unwrap() {
+                image.attributes.borrow_mut().insert("loading", "lazy".to_string());
+            }
+            Ok(())
+        }
+    }
+
+    let mut transforms = Transforms::default();
+    transforms.register(LazyImages);
+    let html = transforms.apply(r#"<h2>Title</h2><p><img src="a.png"></p>"#).unwrap();
+    assert_eq!(
+        html,
+        r##"<h2 id="title"><a href="#title">Title</a></h2><p><img loading="lazy" src="a.png"></p>"##
+    );
+}
+
+#[test]
+fn nested_table_of_contents() {
+    let html = concat!(
+        "<h1>Title</h1><h3>Before</h3><h2>Setup</h2><h4>Deep</h4><h3>Install &amp; <code>run</code></h3>",
+        "<h2>Benchmarks</h2><h5>Ignored</h5><h2>Benchmarks</h2>",
+    );
+    let html = apply(HeadingAnchors, html);
+    let toc = TableOfContents::from_html(&html).unwrap();
+    assert_eq!(toc.len(), 6);
+    assert_eq!(
+        toc.to_html().unwrap(),
+        concat!(
+            r##"<ul><li><a href="#before">Before</a></li>"##,
+            r##"<li><a href="#setup">Setup</a><ul><li><a href="#deep">Deep</a></li>"##,
+            r##"<li><a href="#install--run">Install &amp; run</a></li></ul></li>"##,
+            r##"<li><a href="#benchmarks">Benchmarks</a></li>"##,
+            r##"<li><a href="#benchmarks-1">Benchmarks</a></li></ul>"##,
+        )
+    );
+}